@@ -0,0 +1,169 @@
+//! Records toolchain and build-profile facts for `environment` in the JSON
+//! output; read back with `env!` in `src/environment.rs`.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let profile = profile_name();
+    let settings = profile_settings(&profile);
+
+    let flags = env::var("CARGO_ENCODED_RUSTFLAGS").unwrap_or_default();
+    let target_cpu = target_cpu(&flags).unwrap_or_else(|| "default".to_string());
+
+    set("OU_BUILD_RUSTC_VERSION", &rustc_version);
+    set("OU_BUILD_TARGET", &env::var("TARGET").unwrap_or_default());
+    set("OU_BUILD_TARGET_CPU", &target_cpu);
+    set(
+        "OU_BUILD_TARGET_FEATURES",
+        &env::var("CARGO_CFG_TARGET_FEATURE").unwrap_or_default(),
+    );
+    set("OU_BUILD_PROFILE", &profile);
+    set(
+        "OU_BUILD_OPT_LEVEL",
+        &env::var("OPT_LEVEL").unwrap_or_default(),
+    );
+    set("OU_BUILD_LTO", &settings.lto);
+    set("OU_BUILD_CODEGEN_UNITS", &settings.codegen_units);
+    set("OU_BUILD_PANIC", &settings.panic);
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    for profile in &settings.chain {
+        for key in ["LTO", "CODEGEN_UNITS", "PANIC"] {
+            println!(
+                "cargo:rerun-if-env-changed=CARGO_PROFILE_{}_{}",
+                env_name(profile),
+                key
+            );
+        }
+    }
+}
+
+/// Name of the profile being built. `PROFILE` is only ever `debug` or
+/// `release`, the base a custom profile inherits from, so take the output
+/// directory instead: `OUT_DIR` is `<target>/<profile dir>/build/<pkg>/out`.
+fn profile_name() -> String {
+    let dir = env::var("OUT_DIR").ok().and_then(|out| {
+        Path::new(&out)
+            .ancestors()
+            .nth(3)
+            .and_then(Path::file_name)
+            .map(|d| d.to_string_lossy().into_owned())
+    });
+    let dir = dir.unwrap_or_else(|| env::var("PROFILE").unwrap_or_default());
+    // The dev profile builds into `debug`.
+    if dir == "debug" {
+        "dev".to_string()
+    } else {
+        dir
+    }
+}
+
+fn env_name(profile: &str) -> String {
+    profile.to_uppercase().replace('-', "_")
+}
+
+struct ProfileSettings {
+    /// The profile followed by the profiles it inherits from.
+    chain: Vec<String>,
+    lto: String,
+    codegen_units: String,
+    panic: String,
+}
+
+fn set(key: &str, value: &str) {
+    println!("cargo:rustc-env={}={}", key, value);
+}
+
+/// `lto`, `codegen-units` and `panic` of `[profile.<section>]`, following
+/// `inherits`, with Cargo's environment overrides and defaults.
+fn profile_settings(section: &str) -> ProfileSettings {
+    let manifest = fs::read_to_string("Cargo.toml").unwrap_or_default();
+    let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            let name = line
+                .strip_prefix("[profile.")
+                .and_then(|l| l.strip_suffix(']'));
+            sections.push((name.unwrap_or("").to_string(), Vec::new()));
+        } else if let (Some((_, keys)), Some((k, v))) = (sections.last_mut(), line.split_once('='))
+        {
+            keys.push((k.trim().to_string(), v.trim().trim_matches('"').to_string()));
+        }
+    }
+    let lookup = |profile: &str, key: &str| {
+        sections
+            .iter()
+            .filter(|(name, _)| name == profile)
+            .flat_map(|(_, keys)| keys)
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    };
+
+    // Custom profiles name their parent; dev and release are the roots.
+    let mut chain = vec![section.to_string()];
+    while chain.len() < 8 {
+        let last = chain.last().unwrap();
+        let parent = match last.as_str() {
+            "dev" | "release" => break,
+            "test" => Some("dev".to_string()),
+            "bench" => Some("release".to_string()),
+            other => lookup(other, "inherits"),
+        };
+        match parent {
+            Some(p) if !chain.contains(&p) => chain.push(p),
+            _ => break,
+        }
+    }
+    let base = chain.last().unwrap().clone();
+
+    let get = |key: &str, env_key: &str, default: &str| {
+        chain
+            .iter()
+            .find_map(|p| {
+                env::var(format!("CARGO_PROFILE_{}_{}", env_name(p), env_key))
+                    .ok()
+                    .or_else(|| lookup(p, key))
+            })
+            .unwrap_or_else(|| default.to_string())
+    };
+    ProfileSettings {
+        lto: get("lto", "LTO", "false"),
+        codegen_units: get(
+            "codegen-units",
+            "CODEGEN_UNITS",
+            if base == "dev" { "256" } else { "16" },
+        ),
+        panic: get("panic", "PANIC", "unwind"),
+        chain,
+    }
+}
+
+/// Last `target-cpu` in the 0x1f-separated RUSTFLAGS.
+fn target_cpu(flags: &str) -> Option<String> {
+    let args: Vec<&str> = flags.split('\x1f').collect();
+    let mut cpu = None;
+    for (i, arg) in args.iter().enumerate() {
+        let opt = match arg.strip_prefix("-C") {
+            Some("") => args.get(i + 1).copied().unwrap_or(""),
+            Some(rest) => rest,
+            None => continue,
+        };
+        if let Some(v) = opt.strip_prefix("target-cpu=") {
+            cpu = Some(v.to_string());
+        }
+    }
+    cpu
+}
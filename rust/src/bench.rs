@@ -0,0 +1,616 @@
+//! Warmup and timed-run driver; allocation and prefill stay outside the timed region.
+
+use crate::clock::{Clock, ClockKind, ClockStats};
+use crate::normal::{BoxMuller, InvCdf, NormalKind, NormalPolar, NormalSampler, Ziggurat};
+use crate::ou::{self, OuParams, Scheme, TerminalStats};
+use crate::parallel;
+use crate::perf::{PerfCounts, PerfError, Phase, Probe};
+use crate::rng::{Pcg32, RngKind, SplitMix32, UniformRng, WyRand, XorShift128, Xoshiro256StarStar};
+use crate::sched::{self, CpuTrace, Priority};
+use crate::stats::{Outliers, Summary};
+use crate::stop::{Adaptive, StopReason, StopRule, Warmup, WarmupRule};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Full,
+    Gn,
+    Ou,
+}
+
+impl Mode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Mode::Full => "full",
+            Mode::Gn => "gn",
+            Mode::Ou => "ou",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    pub n: usize,
+    /// Independent paths per run, each with its own generator stream.
+    pub paths: usize,
+    /// Worker threads; paths are split across them, or with a single path,
+    /// contiguous chunks of it.
+    pub threads: usize,
+    pub runs: usize,
+    pub warmup: Warmup,
+    pub seed: u32,
+    pub mode: Mode,
+    pub rng: RngKind,
+    pub normal: NormalKind,
+    pub scheme: Scheme,
+    pub params: OuParams,
+    /// Drop severe outliers from avg, median, min, max and the summary.
+    pub reject_outliers: bool,
+    /// When set, `runs` is ignored and the run count is chosen adaptively.
+    pub adaptive: Option<Adaptive>,
+    /// Timestamp source for warmup and timed runs.
+    pub clock: ClockKind,
+    /// Subtract the measured per-read clock overhead from every phase time.
+    pub subtract_timer_overhead: bool,
+    /// Core to pin the process to before running; single-threaded runs only.
+    pub pin_cpu: Option<usize>,
+    /// Scheduling policy to switch to before running; unchanged if `None`.
+    pub priority: Option<Priority>,
+    /// Count hardware events per phase of the timed runs.
+    pub perf_counters: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            n: 500_000,
+            paths: 1,
+            threads: 1,
+            runs: 1000,
+            warmup: Warmup::Fixed(5),
+            seed: 1,
+            mode: Mode::Full,
+            rng: RngKind::XorShift128,
+            normal: NormalKind::Polar,
+            scheme: Scheme::Euler,
+            params: OuParams::default(),
+            reject_outliers: false,
+            adaptive: None,
+            clock: ClockKind::Instant,
+            subtract_timer_overhead: false,
+            pin_cpu: None,
+            priority: None,
+            perf_counters: false,
+        }
+    }
+}
+
+impl Config {
+    /// Threads that actually run: at most one per path, or per step of a
+    /// single path.
+    pub fn effective_threads(&self) -> usize {
+        let units = if self.paths > 1 {
+            self.paths
+        } else {
+            self.n - 1
+        };
+        self.threads.min(units).max(1)
+    }
+}
+
+/// Phase times of one timed run, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunSample {
+    pub gen_s: f64,
+    pub sim_s: f64,
+    pub chk_s: f64,
+    pub run_s: f64,
+}
+
+/// Aggregated timings of the timed runs, all in seconds.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub total_s: f64,
+    pub total_gen_s: f64,
+    pub total_sim_s: f64,
+    pub total_chk_s: f64,
+    pub min_s: f64,
+    pub max_s: f64,
+    pub median_s: f64,
+    /// Per-run wall times, sorted ascending.
+    pub run_times: Vec<f64>,
+    /// Per-run phase times, in run order.
+    pub samples: Vec<RunSample>,
+    /// Distributions of the phase times over every timed run, in
+    /// `compare::METRICS` order after `median`.
+    pub phases: [Summary; 3],
+    /// Distribution of `run_times`, without severe outliers when they
+    /// have been rejected.
+    pub summary: Summary,
+    /// Tukey-fence classification of `run_times`.
+    pub outliers: Outliers,
+    pub checksum: f64,
+    /// Terminal values of the last run; `None` in `gn` mode.
+    pub terminal: Option<TerminalStats>,
+    /// Worker threads actually used; may be below `Config::threads` when
+    /// there is less work than threads.
+    pub threads: usize,
+    /// Warmup iterations executed.
+    pub warmup_runs: usize,
+    /// For `--warmup=auto`, whether the iteration times stabilized before
+    /// the cap.
+    pub warmup_converged: Option<bool>,
+    /// Why the timed loop ended; `run_times.len()` is the number of runs.
+    pub stop: StopReason,
+    /// Comparison against a single-threaded run of the same config; set
+    /// when more than one thread was used.
+    pub scaling: Option<Scaling>,
+    /// Hardware event counts of the timing thread, or why they are
+    /// unavailable; `None` unless [`Config::perf_counters`] is set.
+    pub perf: Option<Result<PerfCounts, PerfError>>,
+    /// Overhead and resolution of [`Config::clock`], measured before the
+    /// timed runs.
+    pub clock: ClockStats,
+    /// Whether `clock.overhead_s` was subtracted from each phase time.
+    pub overhead_subtracted: bool,
+    /// Cores the timing thread ran the timed runs on.
+    pub cpus: CpuTrace,
+}
+
+/// Parallel speedup, as serial time over threaded time.
+#[derive(Debug, Clone, Copy)]
+pub struct Scaling {
+    /// Timed runs of the single-threaded reference.
+    pub serial_runs: usize,
+    pub serial_median_s: f64,
+    /// Median run time speedup.
+    pub speedup: f64,
+    /// `speedup / threads`.
+    pub efficiency: f64,
+    pub gen_speedup: f64,
+    pub sim_speedup: f64,
+    pub chk_speedup: f64,
+}
+
+impl Scaling {
+    pub fn new(serial: &Report, parallel: &Report) -> Self {
+        let speedup = serial.median_s / parallel.median_s;
+        // The two sides run different counts, so compare per-run means.
+        let (sr, pr) = (
+            serial.run_times.len() as f64,
+            parallel.run_times.len() as f64,
+        );
+        Self {
+            serial_runs: serial.run_times.len(),
+            serial_median_s: serial.median_s,
+            speedup,
+            efficiency: speedup / parallel.threads as f64,
+            gen_speedup: (serial.total_gen_s / sr) / (parallel.total_gen_s / pr),
+            sim_speedup: (serial.total_sim_s / sr) / (parallel.total_sim_s / pr),
+            chk_speedup: (serial.total_chk_s / sr) / (parallel.total_chk_s / pr),
+        }
+    }
+}
+
+/// Per-step rates derived from the breakdown totals, averaged over the
+/// timed runs. Phases the mode skips are NaN.
+#[derive(Debug, Clone, Copy)]
+pub struct Throughput {
+    pub ns_per_normal: f64,
+    pub ns_per_ou_step: f64,
+    pub normals_per_s: f64,
+    /// Bytes of `gn` read plus `ou` written by the simulate phase, per second.
+    pub sim_bytes_per_s: f64,
+    /// Bytes read by the checksum phase, per second.
+    pub chk_bytes_per_s: f64,
+}
+
+impl Throughput {
+    pub fn new(cfg: &Config, report: &Report) -> Self {
+        let runs = report.run_times.len() as f64;
+        let paths = cfg.paths as f64;
+        let steps = (cfg.n - 1) as f64 * paths;
+        let f64_bytes = std::mem::size_of::<f64>() as f64;
+        let per_run = |total_s: f64, ran: bool| if ran { total_s / runs } else { f64::NAN };
+        let gen_s = per_run(report.total_gen_s, cfg.mode != Mode::Ou);
+        let sim_s = per_run(report.total_sim_s, cfg.mode != Mode::Gn);
+        let chk_s = per_run(report.total_chk_s, true);
+        let chk_len = match cfg.mode {
+            Mode::Gn => steps,
+            Mode::Full | Mode::Ou => cfg.n as f64 * paths,
+        };
+        Self {
+            ns_per_normal: gen_s / steps * 1e9,
+            ns_per_ou_step: sim_s / steps * 1e9,
+            normals_per_s: steps / gen_s,
+            sim_bytes_per_s: (steps + cfg.n as f64 * paths) * f64_bytes / sim_s,
+            chk_bytes_per_s: chk_len * f64_bytes / chk_s,
+        }
+    }
+}
+
+impl Report {
+    pub fn avg_s(&self) -> f64 {
+        if self.outliers.rejected > 0 {
+            return self.summary.mean;
+        }
+        self.total_s / self.run_times.len() as f64
+    }
+
+    /// Recomputes avg, median, min, max and the summary without severe
+    /// outliers. Totals and `run_times` keep every run.
+    pub fn reject_severe_outliers(&mut self) {
+        let kept = self.outliers.without_severe(&self.run_times);
+        self.outliers.rejected = self.run_times.len() - kept.len();
+        if self.outliers.rejected == 0 {
+            return;
+        }
+        self.summary = Summary::from_sorted(kept);
+        self.median_s = self.summary.median;
+        self.min_s = self.summary.min;
+        self.max_s = self.summary.max;
+    }
+}
+
+/// Timed runs of the single-threaded reference behind [`Report::scaling`].
+pub const SERIAL_REFERENCE_RUNS: usize = 20;
+
+/// Runs the benchmark with the generator and sampler named by `cfg.rng`
+/// and `cfg.normal`. With more than one thread, a single-threaded
+/// reference of at most [`SERIAL_REFERENCE_RUNS`] fixed runs is made first
+/// to fill in [`Report::scaling`]; it does not count against an adaptive
+/// time budget.
+pub fn run(cfg: &Config) -> Report {
+    apply_sched(cfg);
+    if cfg.threads <= 1 {
+        return finish(cfg, dispatch(cfg));
+    }
+    let serial_cfg = Config {
+        threads: 1,
+        runs: cfg.runs.min(SERIAL_REFERENCE_RUNS),
+        adaptive: None,
+        perf_counters: false,
+        ..*cfg
+    };
+    let serial = finish(cfg, dispatch(&serial_cfg));
+    let mut report = finish(cfg, dispatch(cfg));
+    if report.threads > 1 {
+        report.scaling = Some(Scaling::new(&serial, &report));
+    }
+    report
+}
+
+/// Applies `cfg.pin_cpu` and `cfg.priority` to the calling thread; worker
+/// threads spawned afterwards inherit them.
+fn apply_sched(cfg: &Config) {
+    if let Some(cpu) = cfg.pin_cpu {
+        sched::pin_to_cpu(cpu).unwrap_or_else(|e| panic!("cannot pin to cpu {}: {}", cpu, e));
+    }
+    if let Some(p) = cfg.priority {
+        sched::set_priority(p)
+            .unwrap_or_else(|e| panic!("cannot set priority {}: {}", p.as_str(), e));
+    }
+}
+
+fn finish(cfg: &Config, mut report: Report) -> Report {
+    if cfg.reject_outliers {
+        report.reject_severe_outliers();
+    }
+    report
+}
+
+fn dispatch(cfg: &Config) -> Report {
+    match cfg.rng {
+        RngKind::XorShift128 => run_rng::<XorShift128>(cfg),
+        RngKind::SplitMix32 => run_rng::<SplitMix32>(cfg),
+        RngKind::Pcg32 => run_rng::<Pcg32>(cfg),
+        RngKind::Xoshiro256StarStar => run_rng::<Xoshiro256StarStar>(cfg),
+        RngKind::WyRand => run_rng::<WyRand>(cfg),
+    }
+}
+
+fn run_rng<R: UniformRng + Send>(cfg: &Config) -> Report {
+    match cfg.normal {
+        NormalKind::Polar => run_with::<R, NormalPolar>(cfg),
+        NormalKind::Ziggurat => run_with::<R, Ziggurat>(cfg),
+        NormalKind::InvCdf => run_with::<R, InvCdf>(cfg),
+        NormalKind::BoxMuller => run_with::<R, BoxMuller>(cfg),
+    }
+}
+
+/// Runs the benchmark with a statically chosen generator and sampler;
+/// `cfg.rng` and `cfg.normal` are ignored.
+pub fn run_with<R: UniformRng + Send, S: NormalSampler + Send>(cfg: &Config) -> Report {
+    assert!(cfg.n >= 2, "n must be >= 2");
+    assert!(cfg.runs >= 1, "runs must be >= 1");
+    if let Some(ad) = cfg.adaptive {
+        assert!(ad.max_runs >= 1, "max_runs must be >= 1");
+    }
+    assert!(cfg.paths >= 1, "paths must be >= 1");
+    assert!(cfg.threads >= 1, "threads must be >= 1");
+
+    if cfg.threads > 1 {
+        return parallel::run_threaded::<R, S>(cfg);
+    }
+
+    let clock = new_clock(cfg.clock);
+
+    let n = cfg.n;
+    let paths = cfg.paths;
+    let steps = n - 1;
+    let c = cfg.params.coefficients(cfg.scheme, n);
+    let (a, b, diff) = (c.a, c.b, c.diff);
+    let x0 = cfg.params.x0;
+
+    // Path-major: path p owns gn[p * steps..] and ou[p * n..].
+    let mut gn = vec![0.0_f64; paths * steps];
+    let mut ou = vec![0.0_f64; paths * n];
+
+    if let Mode::Ou = cfg.mode {
+        let (mut rng_prefill, mut norm_prefill) = streams::<R, S>(cfg.seed, paths);
+        ou::gen_normals_paths(&mut gn, steps, diff, &mut norm_prefill, &mut rng_prefill);
+    }
+
+    // Warmup
+    let mut warm = WarmupRule::new(cfg.warmup);
+    let mut warm_times = Vec::new();
+    {
+        let (mut rng, mut norm) = streams::<R, S>(cfg.seed, paths);
+        while !warm.done(&warm_times) {
+            let t0 = clock.now();
+            let s = match cfg.mode {
+                Mode::Full => {
+                    ou::gen_normals_paths(&mut gn, steps, diff, &mut norm, &mut rng);
+                    ou::simulate_paths(&mut ou, &gn, n, a, b, x0);
+                    ou::checksum(&ou)
+                }
+                Mode::Gn => {
+                    ou::gen_normals_paths(&mut gn, steps, diff, &mut norm, &mut rng);
+                    ou::checksum(&gn)
+                }
+                Mode::Ou => {
+                    ou::simulate_paths(&mut ou, &gn, n, a, b, x0);
+                    ou::checksum(&ou)
+                }
+            };
+            if s == 123456789.0 {
+                eprintln!("impossible");
+            }
+            warm_times.push(clock.secs(t0, clock.now()));
+        }
+    }
+
+    // Timed runs
+    let clock_stats = clock.stats();
+    let (mut rng, mut norm) = streams::<R, S>(cfg.seed, paths);
+
+    let mut timings = Timings::new(cfg.runs, timer_overhead(cfg, &clock_stats));
+    let mut checksum = 0.0_f64;
+    let mut stop = StopRule::new(cfg.runs, cfg.adaptive);
+    let mut probe = Probe::new(cfg.perf_counters);
+    let mut cpus = CpuTrace::default();
+
+    while !stop.done(timings.run_times()) {
+        let (gen, sim, chk, run);
+        match cfg.mode {
+            Mode::Full => {
+                probe.start();
+                let t0 = clock.now();
+                ou::gen_normals_paths(&mut gn, steps, diff, &mut norm, &mut rng);
+                let t1 = clock.now();
+                probe.end(Phase::Gen);
+                ou::simulate_paths(&mut ou, &gn, n, a, b, x0);
+                let t2 = clock.now();
+                probe.end(Phase::Sim);
+                checksum += ou::checksum(&ou);
+                let t3 = clock.now();
+                probe.end(Phase::Chk);
+
+                gen = clock.secs(t0, t1);
+                sim = clock.secs(t1, t2);
+                chk = clock.secs(t2, t3);
+                run = clock.secs(t0, t3);
+            }
+            Mode::Gn => {
+                probe.start();
+                let t0 = clock.now();
+                ou::gen_normals_paths(&mut gn, steps, diff, &mut norm, &mut rng);
+                let t1 = clock.now();
+                probe.end(Phase::Gen);
+                checksum += ou::checksum(&gn);
+                let t2 = clock.now();
+                probe.end(Phase::Chk);
+
+                gen = clock.secs(t0, t1);
+                sim = 0.0_f64;
+                chk = clock.secs(t1, t2);
+                run = clock.secs(t0, t2);
+            }
+            Mode::Ou => {
+                probe.start();
+                let t0 = clock.now();
+                ou::simulate_paths(&mut ou, &gn, n, a, b, x0);
+                let t1 = clock.now();
+                probe.end(Phase::Sim);
+                checksum += ou::checksum(&ou);
+                let t2 = clock.now();
+                probe.end(Phase::Chk);
+
+                gen = 0.0_f64;
+                sim = clock.secs(t0, t1);
+                chk = clock.secs(t1, t2);
+                run = clock.secs(t0, t2);
+            }
+        }
+
+        timings.record(gen, sim, chk, run);
+        cpus.sample();
+    }
+
+    let terminal = match cfg.mode {
+        Mode::Gn => None,
+        Mode::Full | Mode::Ou => Some(ou::terminal_stats(&ou, n)),
+    };
+
+    let mut report = timings.into_report(checksum, terminal, 1, stop.reason);
+    report.perf = probe.finish();
+    report.clock = clock_stats;
+    report.overhead_subtracted = cfg.subtract_timer_overhead;
+    report.cpus = cpus;
+    report.warmup_runs = warm_times.len();
+    report.warmup_converged = warm.converged;
+    report
+}
+
+/// Accumulates per-run phase times in the order the serial loop always has.
+pub(crate) struct Timings {
+    /// Subtracted from each phase time; 0 unless requested.
+    overhead_s: f64,
+    total_s: f64,
+    total_gen_s: f64,
+    total_sim_s: f64,
+    total_chk_s: f64,
+    min_s: f64,
+    max_s: f64,
+    run_times: Vec<f64>,
+    samples: Vec<RunSample>,
+}
+
+impl Timings {
+    pub(crate) fn new(runs: usize, overhead_s: f64) -> Self {
+        Self {
+            overhead_s,
+            total_s: 0.0,
+            total_gen_s: 0.0,
+            total_sim_s: 0.0,
+            total_chk_s: 0.0,
+            min_s: f64::INFINITY,
+            max_s: 0.0,
+            run_times: Vec::with_capacity(runs),
+            samples: Vec::with_capacity(runs),
+        }
+    }
+
+    /// Run times so far, in run order.
+    pub(crate) fn run_times(&self) -> &[f64] {
+        &self.run_times
+    }
+
+    #[inline(always)]
+    pub(crate) fn record(&mut self, gen: f64, sim: f64, chk: f64, run: f64) {
+        let (gen, sim, chk, run) = if self.overhead_s > 0.0 {
+            // Each timed phase spans one clock read; phases the mode skips
+            // are 0 and stay 0. The run loses what its phases lost.
+            let ov = self.overhead_s;
+            let (g, s, c) = (
+                (gen - ov).max(0.0),
+                (sim - ov).max(0.0),
+                (chk - ov).max(0.0),
+            );
+            (g, s, c, run - ((gen - g) + (sim - s) + (chk - c)))
+        } else {
+            (gen, sim, chk, run)
+        };
+        self.total_gen_s += gen;
+        self.total_sim_s += sim;
+        self.total_chk_s += chk;
+        self.total_s += run;
+        self.run_times.push(run);
+        self.samples.push(RunSample {
+            gen_s: gen,
+            sim_s: sim,
+            chk_s: chk,
+            run_s: run,
+        });
+
+        if run < self.min_s {
+            self.min_s = run;
+        }
+        if run > self.max_s {
+            self.max_s = run;
+        }
+    }
+
+    pub(crate) fn into_report(
+        mut self,
+        checksum: f64,
+        terminal: Option<TerminalStats>,
+        threads: usize,
+        stop: StopReason,
+    ) -> Report {
+        self.run_times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let median_s = median_sorted(&self.run_times);
+
+        Report {
+            total_s: self.total_s,
+            total_gen_s: self.total_gen_s,
+            total_sim_s: self.total_sim_s,
+            total_chk_s: self.total_chk_s,
+            min_s: self.min_s,
+            max_s: self.max_s,
+            median_s,
+            summary: Summary::from_sorted(&self.run_times),
+            outliers: Outliers::from_sorted(&self.run_times),
+            phases: phase_summaries(&self.samples),
+            run_times: self.run_times,
+            samples: self.samples,
+            checksum,
+            terminal,
+            threads,
+            scaling: None,
+            perf: None,
+            clock: ClockStats::default(),
+            overhead_subtracted: false,
+            cpus: CpuTrace::default(),
+            warmup_runs: 0,
+            warmup_converged: None,
+            stop,
+        }
+    }
+}
+
+/// Summaries of the generation, simulation and checksum times of `samples`.
+fn phase_summaries(samples: &[RunSample]) -> [Summary; 3] {
+    let summary = |f: fn(&RunSample) -> f64| {
+        let mut xs: Vec<f64> = samples.iter().map(f).collect();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        Summary::from_sorted(&xs)
+    };
+    [
+        summary(|s| s.gen_s),
+        summary(|s| s.sim_s),
+        summary(|s| s.chk_s),
+    ]
+}
+
+/// Per-phase overhead to subtract from the timings, if requested.
+pub(crate) fn timer_overhead(cfg: &Config, stats: &ClockStats) -> f64 {
+    if cfg.subtract_timer_overhead {
+        stats.overhead_s
+    } else {
+        0.0
+    }
+}
+
+pub(crate) fn new_clock(kind: ClockKind) -> Clock {
+    Clock::new(kind).unwrap_or_else(|e| panic!("clock {} unavailable: {}", kind.as_str(), e))
+}
+
+/// Per-path generators and samplers; path `p` uses substream `p` of `seed`.
+pub(crate) fn streams<R: UniformRng, S: NormalSampler>(
+    seed: u32,
+    paths: usize,
+) -> (Vec<R>, Vec<S>) {
+    let rngs = (0..paths).map(|p| R::stream(seed, p)).collect();
+    let norms = (0..paths).map(|_| S::default()).collect();
+    (rngs, norms)
+}
+
+/// Median of an ascending, non-empty slice.
+pub fn median_sorted(xs: &[f64]) -> f64 {
+    let len = xs.len();
+    if len % 2 == 1 {
+        xs[len / 2]
+    } else {
+        (xs[len / 2 - 1] + xs[len / 2]) / 2.0
+    }
+}
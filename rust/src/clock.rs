@@ -0,0 +1,234 @@
+//! Timestamp sources for the timed loop.
+//!
+//! Every clock reads as a `u64` tick count converted to seconds with a
+//! fixed scale, so the timed loop is the same for all of them. The TSC is
+//! calibrated against [`Instant`] once per process; the CPU-time clocks
+//! count only while the thread (or any thread of the process) is running.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Clock selected with `--clock`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockKind {
+    Instant,
+    Tsc,
+    ThreadCpu,
+    ProcessCpu,
+}
+
+impl ClockKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ClockKind::Instant => "instant",
+            ClockKind::Tsc => "tsc",
+            ClockKind::ThreadCpu => "thread-cputime",
+            ClockKind::ProcessCpu => "process-cputime",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Source {
+    Instant(Instant),
+    /// Whether `rdtscp` is available; otherwise `rdtsc` between fences.
+    Tsc(bool),
+    ThreadCpu,
+    ProcessCpu,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    kind: ClockKind,
+    source: Source,
+    secs_per_tick: f64,
+}
+
+/// Measured cost and granularity of a clock.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ClockStats {
+    pub ticks_per_s: f64,
+    /// Mean cost of one reading, from back-to-back reads.
+    pub overhead_s: f64,
+    /// Smallest nonzero step seen between back-to-back reads; NaN if the
+    /// clock never advanced.
+    pub resolution_s: f64,
+}
+
+/// Back-to-back reads taken by [`Clock::stats`].
+const STAT_READS: usize = 10_000;
+
+/// How long the TSC is compared against [`Instant`].
+const TSC_CALIBRATION: Duration = Duration::from_millis(20);
+
+static TSC_HZ: OnceLock<f64> = OnceLock::new();
+
+impl Clock {
+    /// Fails if the clock does not exist on this platform.
+    pub fn new(kind: ClockKind) -> Result<Self, String> {
+        let (source, secs_per_tick) = match kind {
+            ClockKind::Instant => (Source::Instant(Instant::now()), 1e-9),
+            ClockKind::Tsc => {
+                let rdtscp = sys::has_rdtscp()?;
+                let hz = *TSC_HZ.get_or_init(|| calibrate_tsc(rdtscp));
+                (Source::Tsc(rdtscp), 1.0 / hz)
+            }
+            ClockKind::ThreadCpu => {
+                sys::cpu_time_ns(sys::CLOCK_THREAD_CPUTIME_ID)?;
+                (Source::ThreadCpu, 1e-9)
+            }
+            ClockKind::ProcessCpu => {
+                sys::cpu_time_ns(sys::CLOCK_PROCESS_CPUTIME_ID)?;
+                (Source::ProcessCpu, 1e-9)
+            }
+        };
+        Ok(Self {
+            kind,
+            source,
+            secs_per_tick,
+        })
+    }
+
+    pub fn kind(&self) -> ClockKind {
+        self.kind
+    }
+
+    #[inline(always)]
+    pub fn now(&self) -> u64 {
+        match self.source {
+            Source::Instant(base) => base.elapsed().as_nanos() as u64,
+            Source::Tsc(rdtscp) => sys::tsc(rdtscp),
+            Source::ThreadCpu => sys::cpu_time_ns(sys::CLOCK_THREAD_CPUTIME_ID).unwrap_or(0),
+            Source::ProcessCpu => sys::cpu_time_ns(sys::CLOCK_PROCESS_CPUTIME_ID).unwrap_or(0),
+        }
+    }
+
+    /// Seconds from `t0` to `t1`; 0 if the clock went backwards, as the TSC
+    /// can between unsynchronized cores.
+    #[inline(always)]
+    pub fn secs(&self, t0: u64, t1: u64) -> f64 {
+        t1.saturating_sub(t0) as f64 * self.secs_per_tick
+    }
+
+    pub fn stats(&self) -> ClockStats {
+        let start = self.now();
+        let mut prev = start;
+        let mut min_step = u64::MAX;
+        for _ in 0..STAT_READS {
+            let t = self.now();
+            let step = t.saturating_sub(prev);
+            if step > 0 && step < min_step {
+                min_step = step;
+            }
+            prev = t;
+        }
+        ClockStats {
+            ticks_per_s: 1.0 / self.secs_per_tick,
+            overhead_s: self.secs(start, prev) / STAT_READS as f64,
+            resolution_s: if min_step == u64::MAX {
+                f64::NAN
+            } else {
+                min_step as f64 * self.secs_per_tick
+            },
+        }
+    }
+}
+
+/// TSC ticks per second, measured against [`Instant`] with a busy wait.
+fn calibrate_tsc(rdtscp: bool) -> f64 {
+    let i0 = Instant::now();
+    let c0 = sys::tsc(rdtscp);
+    while i0.elapsed() < TSC_CALIBRATION {}
+    let c1 = sys::tsc(rdtscp);
+    let secs = i0.elapsed().as_secs_f64();
+    c1.wrapping_sub(c0) as f64 / secs
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::os::raw::{c_int, c_long};
+
+    pub(super) const CLOCK_PROCESS_CPUTIME_ID: c_int = 2;
+    pub(super) const CLOCK_THREAD_CPUTIME_ID: c_int = 3;
+
+    #[repr(C)]
+    struct Timespec {
+        tv_sec: c_long,
+        tv_nsec: c_long,
+    }
+
+    extern "C" {
+        fn clock_gettime(clock_id: c_int, tp: *mut Timespec) -> c_int;
+    }
+
+    #[inline(always)]
+    pub(super) fn cpu_time_ns(id: c_int) -> Result<u64, String> {
+        let mut ts = Timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        if unsafe { clock_gettime(id, &mut ts) } != 0 {
+            return Err(format!(
+                "clock_gettime({}) failed: {}",
+                id,
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64)
+    }
+
+    pub(super) use super::tsc::{has_rdtscp, tsc};
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    pub(super) const CLOCK_PROCESS_CPUTIME_ID: i32 = 2;
+    pub(super) const CLOCK_THREAD_CPUTIME_ID: i32 = 3;
+
+    pub(super) fn cpu_time_ns(_id: i32) -> Result<u64, String> {
+        Err("CPU-time clocks need Linux clock_gettime".to_string())
+    }
+
+    pub(super) use super::tsc::{has_rdtscp, tsc};
+}
+
+#[cfg(target_arch = "x86_64")]
+mod tsc {
+    use std::arch::x86_64::{__cpuid, __rdtscp, _mm_lfence, _rdtsc};
+
+    pub(crate) fn has_rdtscp() -> Result<bool, String> {
+        // CPUID.80000001H:EDX[27]
+        let max_ext = __cpuid(0x8000_0000).eax;
+        Ok(max_ext >= 0x8000_0001 && __cpuid(0x8000_0001).edx & (1 << 27) != 0)
+    }
+
+    /// `rdtscp` waits for earlier instructions to retire and the trailing
+    /// `lfence` keeps later ones from starting before the read.
+    #[inline(always)]
+    pub(crate) fn tsc(rdtscp: bool) -> u64 {
+        unsafe {
+            if rdtscp {
+                let mut aux = 0u32;
+                let t = __rdtscp(&mut aux);
+                _mm_lfence();
+                t
+            } else {
+                _mm_lfence();
+                let t = _rdtsc();
+                _mm_lfence();
+                t
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod tsc {
+    pub(crate) fn has_rdtscp() -> Result<bool, String> {
+        Err("--clock=tsc needs an x86_64 CPU".to_string())
+    }
+
+    pub(crate) fn tsc(_rdtscp: bool) -> u64 {
+        unreachable!("no TSC on this target")
+    }
+}
@@ -0,0 +1,390 @@
+//! Comparison of a result against a baseline JSON result.
+//!
+//! Each metric is a median with its 95% confidence interval. A change is
+//! significant when the two intervals do not overlap; baselines that carry
+//! no interval fall back to the size of the change alone. Only significant
+//! regressions larger than the fail threshold fail the comparison.
+
+use crate::bench::{Config, Report};
+use crate::json::JsonValue;
+use crate::stats::Summary;
+
+/// Metric names, in output order; phases match the `breakdown_s` keys.
+pub const METRICS: [&str; 4] = ["median", "gen_normals", "simulate", "checksum"];
+
+/// Median of one metric, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub median: f64,
+    pub ci95: Option<(f64, f64)>,
+}
+
+impl Estimate {
+    pub fn from_summary(s: &Summary) -> Self {
+        Self {
+            median: s.median,
+            ci95: Some(s.median_ci95),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Improved,
+    Regressed,
+    Unchanged,
+}
+
+impl Verdict {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Verdict::Improved => "improved",
+            Verdict::Regressed => "regressed",
+            Verdict::Unchanged => "unchanged",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparison {
+    pub metric: &'static str,
+    pub baseline: Estimate,
+    pub current: Estimate,
+    /// Relative change of the median; positive is slower.
+    pub change: f64,
+    pub verdict: Verdict,
+    /// A regression beyond the fail threshold.
+    pub failed: bool,
+}
+
+impl Comparison {
+    /// `threshold` is a fraction, e.g. 0.05 for 5%.
+    pub fn new(
+        metric: &'static str,
+        baseline: Estimate,
+        current: Estimate,
+        threshold: f64,
+    ) -> Self {
+        let change = (current.median - baseline.median) / baseline.median;
+        let significant = match (baseline.ci95, current.ci95) {
+            (Some((b_lo, b_hi)), Some((c_lo, c_hi))) => c_lo > b_hi || c_hi < b_lo,
+            _ => change.abs() > threshold,
+        };
+        let verdict = if !significant || !change.is_finite() {
+            Verdict::Unchanged
+        } else if change > 0.0 {
+            Verdict::Regressed
+        } else {
+            Verdict::Improved
+        };
+        Self {
+            metric,
+            baseline,
+            current,
+            change,
+            verdict,
+            failed: verdict == Verdict::Regressed && change > threshold,
+        }
+    }
+}
+
+/// Picks the Rust record for `cfg.mode` out of JSON lines, as written by
+/// `--output=json` alone or by `run_all.sh` for every language.
+pub fn find_baseline(text: &str, cfg: &Config) -> Result<JsonValue, String> {
+    let mut found = None;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if !line.starts_with('{') {
+            continue;
+        }
+        let v = crate::json::parse(line).map_err(|e| format!("line {}: {}", i + 1, e))?;
+        let is_rust = v.get("language").and_then(JsonValue::as_str) == Some("Rust");
+        let same_mode = v.get("mode").and_then(JsonValue::as_str) == Some(cfg.mode.as_str());
+        if is_rust && same_mode && v.get("median_ms").is_some() {
+            found = Some(v);
+            break;
+        }
+    }
+    found.ok_or_else(|| format!("no Rust result with mode={}", cfg.mode.as_str()))
+}
+
+/// Settings that make two results incomparable when they differ; keys the
+/// baseline lacks are not checked.
+pub fn config_mismatches(baseline: &JsonValue, cfg: &Config) -> Vec<String> {
+    let mut out = Vec::new();
+    let num = |k: &str| baseline.get(k).and_then(JsonValue::as_f64);
+    let text = |k: &str| baseline.get(k).and_then(JsonValue::as_str);
+    for (k, v) in [
+        ("n", cfg.n),
+        ("paths", cfg.paths),
+        ("threads", cfg.effective_threads()),
+    ] {
+        if num(k).is_some_and(|b| b != v as f64) {
+            out.push(format!("{}: baseline {} vs {}", k, num(k).unwrap(), v));
+        }
+    }
+    for (k, v) in [
+        ("rng", cfg.rng.as_str()),
+        ("normal", cfg.normal.as_str()),
+        ("scheme", cfg.scheme.as_str()),
+    ] {
+        if text(k).is_some_and(|b| b != v) {
+            out.push(format!("{}: baseline {} vs {}", k, text(k).unwrap(), v));
+        }
+    }
+    let clock = baseline
+        .get("clock")
+        .and_then(|c| c.get("name"))
+        .and_then(JsonValue::as_str);
+    if let Some(b) = clock.filter(|&b| b != cfg.clock.as_str()) {
+        out.push(format!("clock: baseline {} vs {}", b, cfg.clock.as_str()));
+    }
+    out
+}
+
+/// Compares `report` against a baseline record on every metric the
+/// baseline has.
+pub fn compare(baseline: &JsonValue, report: &Report, threshold: f64) -> Vec<Comparison> {
+    let ms = |v: &JsonValue, k: &str| v.get(k).and_then(JsonValue::as_f64).map(|x| x / 1000.0);
+    let mut out = Vec::new();
+
+    if let Some(median) = ms(baseline, "median_ms") {
+        let stats = baseline.get("stats_ms");
+        let ci = stats.and_then(|s| Some((ms(s, "median_ci95_lo")?, ms(s, "median_ci95_hi")?)));
+        let current = Estimate {
+            median: report.median_s,
+            ci95: Some(report.summary.median_ci95),
+        };
+        out.push(Comparison::new(
+            METRICS[0],
+            Estimate { median, ci95: ci },
+            current,
+            threshold,
+        ));
+    }
+
+    let runs = baseline.get("runs").and_then(JsonValue::as_f64);
+    for (metric, phase) in METRICS[1..].iter().zip(&report.phases) {
+        // Prefer the phase median; older records only have the total, so
+        // compare means instead.
+        let est = match baseline.get("phases_ms").and_then(|p| p.get(metric)) {
+            Some(p) => ms(p, "median").map(|median| Estimate {
+                median,
+                ci95: ms(p, "ci95_lo").zip(ms(p, "ci95_hi")),
+            }),
+            None => {
+                let total = baseline
+                    .get("breakdown_s")
+                    .and_then(|b| b.get(metric))
+                    .and_then(JsonValue::as_f64);
+                total.zip(runs).map(|(t, r)| Estimate {
+                    median: t / r,
+                    ci95: None,
+                })
+            }
+        };
+        let Some(base) = est else {
+            continue;
+        };
+        // Phases the mode skips are 0 on both sides.
+        if base.median <= 0.0 {
+            continue;
+        }
+        let current = if base.ci95.is_some() {
+            Estimate::from_summary(phase)
+        } else {
+            Estimate {
+                median: phase.mean,
+                ci95: None,
+            }
+        };
+        out.push(Comparison::new(metric, base, current, threshold));
+    }
+    out
+}
+
+/// Worst verdict: any regression, else any improvement, else unchanged.
+pub fn overall(comparisons: &[Comparison]) -> Verdict {
+    let any = |v| comparisons.iter().any(|c| c.verdict == v);
+    if any(Verdict::Regressed) {
+        Verdict::Regressed
+    } else if any(Verdict::Improved) {
+        Verdict::Improved
+    } else {
+        Verdict::Unchanged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::{self, Mode};
+    use crate::clock::ClockKind;
+    use crate::json;
+    use crate::stop::Warmup;
+
+    fn est(median: f64, ci: Option<(f64, f64)>) -> Estimate {
+        Estimate { median, ci95: ci }
+    }
+
+    #[test]
+    fn disjoint_intervals_are_significant() {
+        let base = est(1.0, Some((0.98, 1.02)));
+
+        let c = Comparison::new("median", base, est(0.9, Some((0.88, 0.92))), 0.05);
+        assert_eq!(c.verdict, Verdict::Improved);
+        assert!(!c.failed);
+
+        let c = Comparison::new("median", base, est(1.1, Some((1.08, 1.12))), 0.05);
+        assert_eq!(c.verdict, Verdict::Regressed);
+        assert!(c.failed);
+        assert!((c.change - 0.1).abs() < 1e-12);
+
+        // Significant but within the threshold: reported, not failed.
+        let c = Comparison::new("median", base, est(1.03, Some((1.025, 1.035))), 0.05);
+        assert_eq!(c.verdict, Verdict::Regressed);
+        assert!(!c.failed);
+    }
+
+    #[test]
+    fn overlapping_intervals_are_unchanged_whatever_the_change() {
+        let c = Comparison::new(
+            "median",
+            est(1.0, Some((0.5, 1.5))),
+            est(1.3, Some((1.2, 1.4))),
+            0.05,
+        );
+        assert_eq!(c.verdict, Verdict::Unchanged);
+        assert!(!c.failed);
+    }
+
+    #[test]
+    fn without_a_baseline_interval_the_threshold_decides() {
+        let cur = |m| est(m, Some((m - 0.01, m + 0.01)));
+        let c = Comparison::new("simulate", est(1.0, None), cur(1.04), 0.05);
+        assert_eq!(c.verdict, Verdict::Unchanged);
+        let c = Comparison::new("simulate", est(1.0, None), cur(1.06), 0.05);
+        assert_eq!(c.verdict, Verdict::Regressed);
+        assert!(c.failed);
+        let c = Comparison::new("simulate", est(1.0, None), cur(0.9), 0.05);
+        assert_eq!(c.verdict, Verdict::Improved);
+    }
+
+    #[test]
+    fn non_finite_change_is_unchanged() {
+        let c = Comparison::new("checksum", est(0.0, None), est(1.0, None), 0.05);
+        assert!(!c.change.is_finite());
+        assert_eq!(c.verdict, Verdict::Unchanged);
+        assert!(!c.failed);
+    }
+
+    #[test]
+    fn overall_is_the_worst_verdict() {
+        let base = est(1.0, None);
+        let unchanged = Comparison::new("a", base, est(1.0, None), 0.05);
+        let improved = Comparison::new("b", base, est(0.5, None), 0.05);
+        let regressed = Comparison::new("c", base, est(2.0, None), 0.05);
+        assert_eq!(overall(&[]), Verdict::Unchanged);
+        assert_eq!(overall(&[unchanged, improved]), Verdict::Improved);
+        assert_eq!(
+            overall(&[improved, regressed, unchanged]),
+            Verdict::Regressed
+        );
+    }
+
+    fn small_config(mode: Mode) -> Config {
+        Config {
+            n: 100,
+            runs: 5,
+            warmup: Warmup::Fixed(1),
+            mode,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn compare_skips_phases_the_mode_does_not_run() {
+        let cfg = small_config(Mode::Gn);
+        let report = bench::run(&cfg);
+        let baseline = json::parse(
+            r#"{"median_ms":0.01,"stats_ms":{"median_ci95_lo":0.009,"median_ci95_hi":0.011},
+                "phases_ms":{"gen_normals":{"median":0.008,"ci95_lo":0.007,"ci95_hi":0.009},
+                             "simulate":{"median":0,"ci95_lo":0,"ci95_hi":0},
+                             "checksum":{"median":0.001,"ci95_lo":0.0009,"ci95_hi":0.0011}}}"#,
+        )
+        .unwrap();
+        let metrics: Vec<&str> = compare(&baseline, &report, 0.05)
+            .iter()
+            .map(|c| c.metric)
+            .collect();
+        assert_eq!(metrics, ["median", "gen_normals", "checksum"]);
+    }
+
+    #[test]
+    fn compare_falls_back_to_phase_means_for_old_records() {
+        let cfg = small_config(Mode::Full);
+        let report = bench::run(&cfg);
+        let baseline = json::parse(
+            r#"{"median_ms":0.01,"runs":4,
+                "breakdown_s":{"gen_normals":0.00004,"simulate":0.00002,"checksum":0}}"#,
+        )
+        .unwrap();
+        let out = compare(&baseline, &report, 0.05);
+        let metrics: Vec<&str> = out.iter().map(|c| c.metric).collect();
+        assert_eq!(metrics, ["median", "gen_normals", "simulate"]);
+        assert_eq!(out[0].baseline.ci95, None);
+        assert_eq!(out[1].baseline.median, 0.00001);
+        assert_eq!(out[1].current.ci95, None);
+        // Same mean, summed in sorted rather than run order.
+        let mean = report.total_gen_s / report.run_times.len() as f64;
+        assert!((out[1].current.median - mean).abs() <= 1e-12 * mean);
+    }
+
+    #[test]
+    fn find_baseline_picks_the_rust_record_of_the_mode() {
+        let text = concat!(
+            "== C ==\n",
+            r#"{"language":"C","mode":"full","median_ms":1}"#,
+            "\n",
+            r#"{"language":"Rust","mode":"gn","median_ms":2}"#,
+            "\n",
+            r#"{"language":"Rust","mode":"full","median_ms":3}"#,
+            "\n",
+        );
+        let found = find_baseline(text, &small_config(Mode::Full)).unwrap();
+        assert_eq!(
+            found.get("median_ms").and_then(JsonValue::as_f64),
+            Some(3.0)
+        );
+        let err = find_baseline(text, &small_config(Mode::Ou)).unwrap_err();
+        assert_eq!(err, "no Rust result with mode=ou");
+        assert!(find_baseline("{oops\n", &small_config(Mode::Full))
+            .unwrap_err()
+            .starts_with("line 1: "));
+    }
+
+    #[test]
+    fn config_mismatches_cover_timing_relevant_settings() {
+        let baseline = json::parse(
+            r#"{"n":100,"paths":1,"threads":1,"rng":"xorshift128","normal":"polar",
+                "scheme":"euler","clock":{"name":"instant"}}"#,
+        )
+        .unwrap();
+        let cfg = small_config(Mode::Full);
+        assert!(config_mismatches(&baseline, &cfg).is_empty());
+
+        let other = Config {
+            n: 200,
+            paths: 4,
+            threads: 2,
+            clock: ClockKind::Tsc,
+            ..cfg
+        };
+        let found = config_mismatches(&baseline, &other);
+        let keys: Vec<&str> = found.iter().map(|m| m.split(':').next().unwrap()).collect();
+        assert_eq!(keys, ["n", "paths", "threads", "clock"]);
+
+        // Keys an older baseline lacks are not checked.
+        assert!(config_mismatches(&json::parse("{}").unwrap(), &other).is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+//! Host and build facts recorded with every JSON result.
+//!
+//! Runtime facts come from `/proc` and `/sys` and are `None` where those
+//! do not exist; build facts are captured by `build.rs`.
+
+use std::fs;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Environment {
+    /// UTC, RFC 3339.
+    pub timestamp: String,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub kernel: Option<String>,
+    pub cpu_model: Option<String>,
+    /// Logical CPUs present.
+    pub logical_cpus: Option<usize>,
+    /// CPUs this process may run on.
+    pub available_cpus: Option<usize>,
+    /// `scaling_governor` of cpu0.
+    pub governor: Option<String>,
+    pub build: Build,
+}
+
+/// Toolchain and profile of this binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Build {
+    pub rustc: &'static str,
+    pub target: &'static str,
+    /// `target-cpu` from RUSTFLAGS, or `default`.
+    pub target_cpu: &'static str,
+    /// Enabled target features, comma-separated.
+    pub target_features: &'static str,
+    /// Cargo profile name, custom profiles included; the settings below
+    /// follow its `inherits` chain.
+    pub profile: &'static str,
+    pub opt_level: &'static str,
+    pub lto: &'static str,
+    pub codegen_units: &'static str,
+    pub panic: &'static str,
+}
+
+pub const BUILD: Build = Build {
+    rustc: env!("OU_BUILD_RUSTC_VERSION"),
+    target: env!("OU_BUILD_TARGET"),
+    target_cpu: env!("OU_BUILD_TARGET_CPU"),
+    target_features: env!("OU_BUILD_TARGET_FEATURES"),
+    profile: env!("OU_BUILD_PROFILE"),
+    opt_level: env!("OU_BUILD_OPT_LEVEL"),
+    lto: env!("OU_BUILD_LTO"),
+    codegen_units: env!("OU_BUILD_CODEGEN_UNITS"),
+    panic: env!("OU_BUILD_PANIC"),
+};
+
+impl Environment {
+    pub fn capture() -> Self {
+        let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok();
+        let cpuinfo = cpuinfo.as_deref();
+        Self {
+            timestamp: rfc3339_utc(SystemTime::now()),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            kernel: read_trimmed("/proc/sys/kernel/osrelease"),
+            cpu_model: cpuinfo.and_then(cpu_model),
+            logical_cpus: cpuinfo
+                .map(|s| s.lines().filter(|l| l.starts_with("processor")).count())
+                .filter(|&n| n > 0),
+            available_cpus: thread::available_parallelism().ok().map(|n| n.get()),
+            governor: read_trimmed("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor"),
+            build: BUILD,
+        }
+    }
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// First `model name` (x86) or `Model` (some ARM boards) line.
+fn cpu_model(cpuinfo: &str) -> Option<String> {
+    cpuinfo.lines().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        matches!(k.trim(), "model name" | "Model").then(|| v.trim().to_string())
+    })
+}
+
+fn rfc3339_utc(t: SystemTime) -> String {
+    let secs = t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    let (y, m, d) = civil_from_days(days as i64);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        y,
+        m,
+        d,
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+/// Proleptic Gregorian date of a day count since 1970-01-01
+/// (H. Hinnant, `civil_from_days`).
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
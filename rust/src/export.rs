@@ -0,0 +1,53 @@
+//! Raw per-run sample export for offline analysis.
+
+use std::io::{self, Write};
+
+use crate::bench::RunSample;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    Csv,
+    Ndjson,
+}
+
+impl SampleFormat {
+    /// Picks the format from a file extension: `.csv`, or `.ndjson`/`.jsonl`.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = path.rsplit_once('.')?.1;
+        match ext {
+            "csv" => Some(SampleFormat::Csv),
+            "ndjson" | "jsonl" => Some(SampleFormat::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+/// Writes one record per timed run, in run order, with times in seconds.
+pub fn write_samples<W: Write>(
+    w: &mut W,
+    format: SampleFormat,
+    samples: &[RunSample],
+) -> io::Result<()> {
+    match format {
+        SampleFormat::Csv => {
+            writeln!(w, "run,gen_s,sim_s,chk_s,run_s")?;
+            for (i, s) in samples.iter().enumerate() {
+                writeln!(
+                    w,
+                    "{},{:.9},{:.9},{:.9},{:.9}",
+                    i, s.gen_s, s.sim_s, s.chk_s, s.run_s
+                )?;
+            }
+        }
+        SampleFormat::Ndjson => {
+            for (i, s) in samples.iter().enumerate() {
+                writeln!(
+                    w,
+                    r#"{{"run":{},"gen_s":{:.9},"sim_s":{:.9},"chk_s":{:.9},"run_s":{:.9}}}"#,
+                    i, s.gen_s, s.sim_s, s.chk_s, s.run_s
+                )?;
+            }
+        }
+    }
+    w.flush()
+}
@@ -0,0 +1,441 @@
+//! Minimal JSON object writer for the single-line result records, and a
+//! reader for loading them back.
+
+use std::fmt::Write;
+
+/// Builds one JSON object with keys in insertion order.
+#[derive(Debug, Clone, Default)]
+pub struct JsonObject {
+    buf: String,
+}
+
+impl JsonObject {
+    pub fn new() -> Self {
+        Self { buf: String::new() }
+    }
+
+    fn key(&mut self, k: &str) -> &mut String {
+        self.buf.push(if self.buf.is_empty() { '{' } else { ',' });
+        write_str(&mut self.buf, k);
+        self.buf.push(':');
+        &mut self.buf
+    }
+
+    pub fn str(&mut self, k: &str, v: &str) -> &mut Self {
+        write_str(self.key(k), v);
+        self
+    }
+
+    pub fn int(&mut self, k: &str, v: impl Into<i128>) -> &mut Self {
+        let v: i128 = v.into();
+        let _ = write!(self.key(k), "{}", v);
+        self
+    }
+
+    pub fn bool(&mut self, k: &str, v: bool) -> &mut Self {
+        let _ = write!(self.key(k), "{}", v);
+        self
+    }
+
+    /// Fixed-point number with `prec` decimals; non-finite values become `null`.
+    pub fn fixed(&mut self, k: &str, v: f64, prec: usize) -> &mut Self {
+        let buf = self.key(k);
+        if v.is_finite() {
+            let _ = write!(buf, "{:.*}", prec, v);
+        } else {
+            buf.push_str("null");
+        }
+        self
+    }
+
+    /// Shortest round-trip representation; non-finite values become `null`.
+    pub fn num(&mut self, k: &str, v: f64) -> &mut Self {
+        let buf = self.key(k);
+        if v.is_finite() {
+            let _ = write!(buf, "{}", v);
+        } else {
+            buf.push_str("null");
+        }
+        self
+    }
+
+    pub fn null(&mut self, k: &str) -> &mut Self {
+        self.key(k).push_str("null");
+        self
+    }
+
+    pub fn obj(&mut self, k: &str, v: JsonObject) -> &mut Self {
+        let s = v.finish();
+        self.key(k).push_str(&s);
+        self
+    }
+
+    pub fn arr(&mut self, k: &str, items: Vec<JsonObject>) -> &mut Self {
+        let buf = self.key(k);
+        buf.push('[');
+        for (i, item) in items.into_iter().enumerate() {
+            if i > 0 {
+                buf.push(',');
+            }
+            buf.push_str(&item.finish());
+        }
+        buf.push(']');
+        self
+    }
+
+    /// Inserts pre-serialized JSON verbatim.
+    pub fn raw(&mut self, k: &str, v: &str) -> &mut Self {
+        self.key(k).push_str(v);
+        self
+    }
+
+    pub fn finish(mut self) -> String {
+        if self.buf.is_empty() {
+            self.buf.push('{');
+        }
+        self.buf.push('}');
+        self.buf
+    }
+}
+
+fn write_str(buf: &mut String, s: &str) {
+    buf.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(buf, "\\u{:04x}", c as u32);
+            }
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+}
+
+/// Parsed JSON value; objects keep their keys in file order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    Arr(Vec<JsonValue>),
+    Obj(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Member `k` of an object; `None` for other values or missing keys.
+    pub fn get(&self, k: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Obj(members) => members.iter().find(|(key, _)| key == k).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Num(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// Parses one JSON document; trailing whitespace is allowed, anything else
+/// after the value is an error.
+pub fn parse(s: &str) -> Result<JsonValue, String> {
+    let mut p = Parser {
+        s: s.as_bytes(),
+        pos: 0,
+    };
+    let v = p.value()?;
+    p.ws();
+    if p.pos != p.s.len() {
+        return Err(p.error("trailing characters"));
+    }
+    Ok(v)
+}
+
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, what: &str) -> String {
+        format!("{} at byte {}", what, self.pos)
+    }
+
+    fn ws(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.s.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: u8) -> Result<(), String> {
+        self.ws();
+        if self.s.get(self.pos) != Some(&c) {
+            return Err(self.error(&format!("expected '{}'", c as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, word: &str, v: JsonValue) -> Result<JsonValue, String> {
+        if !self.s[self.pos..].starts_with(word.as_bytes()) {
+            return Err(self.error("invalid literal"));
+        }
+        self.pos += word.len();
+        Ok(v)
+    }
+
+    fn value(&mut self) -> Result<JsonValue, String> {
+        self.ws();
+        match self.s.get(self.pos) {
+            None => Err(self.error("unexpected end of input")),
+            Some(b'n') => self.literal("null", JsonValue::Null),
+            Some(b't') => self.literal("true", JsonValue::Bool(true)),
+            Some(b'f') => self.literal("false", JsonValue::Bool(false)),
+            Some(b'"') => self.string().map(JsonValue::Str),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.ws();
+                if self.s.get(self.pos) == Some(&b']') {
+                    self.pos += 1;
+                    return Ok(JsonValue::Arr(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.ws();
+                    match self.s.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(JsonValue::Arr(items));
+                        }
+                        _ => return Err(self.error("expected ',' or ']'")),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut members = Vec::new();
+                self.ws();
+                if self.s.get(self.pos) == Some(&b'}') {
+                    self.pos += 1;
+                    return Ok(JsonValue::Obj(members));
+                }
+                loop {
+                    self.ws();
+                    let k = self.string()?;
+                    self.eat(b':')?;
+                    members.push((k, self.value()?));
+                    self.ws();
+                    match self.s.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(JsonValue::Obj(members));
+                        }
+                        _ => return Err(self.error("expected ',' or '}'")),
+                    }
+                }
+            }
+            Some(_) => self.number(),
+        }
+    }
+
+    fn number(&mut self) -> Result<JsonValue, String> {
+        let start = self.pos;
+        while let Some(b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') = self.s.get(self.pos) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.s[start..self.pos])
+            .ok()
+            .and_then(|t| t.parse().ok())
+            .map(JsonValue::Num)
+            .ok_or_else(|| self.error("invalid number"))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.s.get(self.pos) != Some(&b'"') {
+            return Err(self.error("expected string"));
+        }
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            match self.s.get(self.pos) {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    let esc = self.s.get(self.pos + 1).copied();
+                    self.pos += 2;
+                    let c = match esc {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let hex = self
+                                .s
+                                .get(self.pos..self.pos + 4)
+                                .and_then(|h| std::str::from_utf8(h).ok())
+                                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                                .ok_or_else(|| self.error("invalid \\u escape"))?;
+                            self.pos += 4;
+                            // Surrogate pairs are not needed for our records.
+                            char::from_u32(hex).unwrap_or('\u{fffd}')
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    let mut tmp = [0u8; 4];
+                    out.extend_from_slice(c.encode_utf8(&mut tmp).as_bytes());
+                }
+                Some(&b) => {
+                    out.push(b);
+                    self.pos += 1;
+                }
+            }
+        }
+        String::from_utf8(out).map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One `--output=json` line, as printed by the binary.
+    const RECORD: &str = r#"{"language":"Rust","mode":"full","n":1000,"paths":1,"threads":1,"runs":5,"stop":"runs","warmup":1,"seed":1,"rng":"xorshift128","normal":"polar","scheme":"euler","t":1,"theta":1,"mu":0,"sigma":0.1,"x0":0,"clock":{"name":"instant","ticks_per_s":1000000000,"overhead_ns":37.617,"resolution_ns":32.000,"overhead_subtracted":false},"sched":{"pin_cpu":null,"priority":null,"cpus":[0],"migrations":0},"total_s":0.000089,"avg_ms":0.017717,"median_ms":0.018623,"min_ms":0.014662,"max_ms":0.020469,"stats_ms":{"stddev":0.002520,"cv":0.142264,"p5":0.014825,"p25":0.015478,"p75":0.019351,"p95":0.020245,"p99":0.020424,"mad":0.001846,"median_ci95_lo":0.014662,"median_ci95_hi":0.020469},"outliers":{"low_severe":0,"low_mild":0,"high_mild":0,"high_severe":0,"rejected":0},"breakdown_s":{"gen_normals":0.000068,"simulate":0.000017,"checksum":0.000004},"phases_ms":{"gen_normals":{"median":0.014558,"ci95_lo":0.010645,"ci95_hi":0.016463},"simulate":{"median":0.003259,"ci95_lo":0.003257,"ci95_hi":0.003521},"checksum":{"median":0.000760,"ci95_lo":0.000747,"ci95_hi":0.000839}},"perf_counters":null,"throughput":{"ns_per_normal":13.6394,"ns_per_ou_step":3.3207,"normals_per_s":73316796,"simulate_gb_per_s":4.8206,"checksum_gb_per_s":10.3439},"checksum":45.57804008748610158,"warmup_converged":null,"scaling":null,"terminal":{"mean":-0.033290364389884856,"variance":null,"time":0.999,"expected_mean":0,"expected_variance":0.004321968876729052},"environment":{"timestamp":"2026-10-16T19:07:12Z","os":"linux","arch":"x86_64","kernel":"6.18.44-fc-v130","cpu_model":"Intel(R) Xeon(R) Processor","logical_cpus":1,"available_cpus":1,"governor":null,"build":{"rustc":"rustc 1.95.0 (59807616e 2026-04-14)","target":"x86_64-unknown-linux-gnu","target_cpu":"default","target_features":"fxsr,sse,sse2","profile":"release","opt_level":"3","lto":"true","codegen_units":"1","panic":"abort"}}}"#;
+
+    #[test]
+    fn parses_a_result_record() {
+        let v = parse(RECORD).unwrap();
+        assert_eq!(v.get("language").and_then(JsonValue::as_str), Some("Rust"));
+        assert_eq!(v.get("n").and_then(JsonValue::as_f64), Some(1000.0));
+        assert_eq!(v.get("perf_counters"), Some(&JsonValue::Null));
+        assert_eq!(
+            v.get("checksum").and_then(JsonValue::as_f64),
+            Some(45.5780400874861)
+        );
+        let ci = v.get("stats_ms").and_then(|s| s.get("median_ci95_hi"));
+        assert!(ci.and_then(JsonValue::as_f64).is_some());
+        let target = v
+            .get("environment")
+            .and_then(|e| e.get("build"))
+            .and_then(|b| b.get("target"))
+            .and_then(JsonValue::as_str);
+        assert_eq!(target, Some("x86_64-unknown-linux-gnu"));
+        assert_eq!(
+            v.get("sched").and_then(|s| s.get("cpus")),
+            Some(&JsonValue::Arr(vec![JsonValue::Num(0.0)]))
+        );
+        let Some(JsonValue::Obj(members)) = v.get("phases_ms") else {
+            panic!("phases_ms is not an object");
+        };
+        let keys: Vec<&str> = members.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["gen_normals", "simulate", "checksum"]);
+    }
+
+    #[test]
+    fn writer_output_parses_back() {
+        let mut inner = JsonObject::new();
+        inner.int("k", -3);
+        let mut o = JsonObject::new();
+        o.str("s", "quote\" back\\slash\nline\ttab\u{1}é")
+            .int("i", u64::MAX)
+            .bool("b", false)
+            .num("nan", f64::NAN)
+            .num("x", 0.1)
+            .fixed("f", 2.0 / 3.0, 3)
+            .null("z")
+            .obj("o", inner.clone())
+            .arr("a", vec![inner, JsonObject::new()])
+            .raw("r", "[1,2]");
+        let v = parse(&o.finish()).unwrap();
+        assert_eq!(
+            v.get("s").and_then(JsonValue::as_str),
+            Some("quote\" back\\slash\nline\ttab\u{1}é")
+        );
+        assert_eq!(
+            v.get("i").and_then(JsonValue::as_f64),
+            Some(u64::MAX as f64)
+        );
+        assert_eq!(v.get("b"), Some(&JsonValue::Bool(false)));
+        assert_eq!(v.get("nan"), Some(&JsonValue::Null));
+        assert_eq!(v.get("x").and_then(JsonValue::as_f64), Some(0.1));
+        assert_eq!(v.get("f").and_then(JsonValue::as_f64), Some(0.667));
+        assert_eq!(v.get("z"), Some(&JsonValue::Null));
+        assert_eq!(
+            v.get("o").and_then(|o| o.get("k")),
+            Some(&JsonValue::Num(-3.0))
+        );
+        let Some(JsonValue::Arr(items)) = v.get("a") else {
+            panic!("a is not an array");
+        };
+        assert_eq!(items[1], JsonValue::Obj(Vec::new()));
+        assert_eq!(
+            v.get("r"),
+            Some(&JsonValue::Arr(vec![
+                JsonValue::Num(1.0),
+                JsonValue::Num(2.0)
+            ]))
+        );
+        assert_eq!(JsonObject::new().finish(), "{}");
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        let v = parse(r#" "a\/b\"c\\d\be\ff\u00e9\u20AC" "#).unwrap();
+        assert_eq!(v.as_str(), Some("a/b\"c\\d\u{8}e\u{c}fé€"));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        for bad in [
+            "",
+            "   ",
+            "{",
+            "[1,",
+            "[1,]",
+            "{\"a\":}",
+            "{\"a\" 1}",
+            "{a:1}",
+            "{\"a\":1,}",
+            "tru",
+            "nul",
+            "\"abc",
+            "\"\\x\"",
+            "\"\\u12\"",
+            "1 2",
+            "{} x",
+            "-",
+            "1.2.3",
+        ] {
+            assert!(parse(bad).is_err(), "accepted {bad:?}");
+        }
+    }
+
+    #[test]
+    fn errors_name_the_byte_offset() {
+        assert_eq!(parse("[1,]").unwrap_err(), "invalid number at byte 3");
+    }
+}
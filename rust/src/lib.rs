@@ -0,0 +1,34 @@
+//! Unified Ornstein-Uhlenbeck benchmark: the generators, normal sampler, OU
+//! kernels and benchmark driver timed by the `ou_bench_unified` binary.
+
+pub mod bench;
+pub mod clock;
+pub mod compare;
+pub mod environment;
+pub mod export;
+pub mod json;
+pub mod normal;
+pub mod ou;
+mod parallel;
+pub mod perf;
+pub mod rng;
+pub mod sched;
+pub mod stats;
+pub mod stop;
+pub mod sweep;
+pub mod verify;
+
+pub use bench::{Config, Mode, Report, RunSample, Scaling, Throughput};
+pub use clock::{Clock, ClockKind, ClockStats};
+pub use compare::{Comparison, Estimate, Verdict};
+pub use environment::Environment;
+pub use normal::{
+    inv_norm_cdf, BoxMuller, InvCdf, NormalKind, NormalPolar, NormalSampler, Ziggurat,
+};
+pub use ou::{Coefficients, OuParams, Scheme, TerminalStats};
+pub use perf::{EventCounts, PerfCounts, PerfError};
+pub use rng::{Pcg32, RngKind, SplitMix32, UniformRng, WyRand, XorShift128, Xoshiro256StarStar};
+pub use sched::{CpuTrace, Priority};
+pub use stats::{Outliers, Summary};
+pub use stop::{Adaptive, StopReason};
+pub use sweep::{LinearFit, SweepPoint};
@@ -1,120 +1,35 @@
 use std::env;
-use std::time::Instant;
-
-#[derive(Clone, Copy)]
-struct SplitMix32 {
-    s: u32,
-}
-
-impl SplitMix32 {
-    #[inline(always)]
-    fn next_u32(&mut self) -> u32 {
-        self.s = self.s.wrapping_add(0x9E37_79B9);
-        let mut z = self.s;
-        z = (z ^ (z >> 16)).wrapping_mul(0x85EB_CA6B);
-        z = (z ^ (z >> 13)).wrapping_mul(0xC2B2_AE35);
-        z ^ (z >> 16)
-    }
-}
-
-#[derive(Clone, Copy)]
-struct XorShift128 {
-    x: u32,
-    y: u32,
-    z: u32,
-    w: u32,
-}
-
-impl XorShift128 {
-    fn new(seed: u32) -> Self {
-        let mut sm = SplitMix32 { s: seed };
-        let x = sm.next_u32();
-        let y = sm.next_u32();
-        let z = sm.next_u32();
-        let mut w = sm.next_u32();
-
-        if (x | y | z | w) == 0 {
-            w = 1;
-        }
-
-        Self { x, y, z, w }
-    }
-
-    #[inline(always)]
-    fn next_u32(&mut self) -> u32 {
-        // Marsaglia xorshift128 (32-bit)
-        let t = self.x ^ (self.x << 11);
-        self.x = self.y;
-        self.y = self.z;
-        self.z = self.w;
-        self.w = self
-            .w
-            ^ (self.w >> 19)
-            ^ t
-            ^ (t >> 8);
-        self.w
-    }
-
-    #[inline(always)]
-    fn next_f64(&mut self) -> f64 {
-        // 53-bit uniform in [0,1) from two u32 draws.
-        let a = self.next_u32();
-        let b = self.next_u32();
-        let u: u64 = ((a >> 5) as u64) << 26 | ((b >> 6) as u64);
-        (u as f64) * (1.0 / 9007199254740992.0) // 2^53
-    }
-}
-
-#[derive(Clone, Copy)]
-struct NormalPolar {
-    has_spare: bool,
-    spare: f64,
-}
-
-impl NormalPolar {
-    fn new() -> Self {
-        Self {
-            has_spare: false,
-            spare: 0.0,
-        }
-    }
-
-    #[inline(always)]
-    fn next_standard(&mut self, rng: &mut XorShift128) -> f64 {
-        if self.has_spare {
-            self.has_spare = false;
-            return self.spare;
-        }
-
-        loop {
-            let u = 2.0 * rng.next_f64() - 1.0;
-            let v = 2.0 * rng.next_f64() - 1.0;
-            let s = u * u + v * v;
-            if s > 0.0 && s < 1.0 {
-                let m = (-2.0 * s.ln() / s).sqrt();
-                self.spare = v * m;
-                self.has_spare = true;
-                return u * m;
-            }
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::process;
+use std::time::Duration;
+
+use ou_bench_unified::bench::{self, Config, Mode, Report, Throughput};
+use ou_bench_unified::clock::ClockKind;
+use ou_bench_unified::compare::{self, Comparison};
+use ou_bench_unified::environment::{self, Environment};
+use ou_bench_unified::export::{self, SampleFormat};
+use ou_bench_unified::json::JsonObject;
+use ou_bench_unified::normal::NormalKind;
+use ou_bench_unified::ou::Scheme;
+use ou_bench_unified::perf::{self, EventCounts};
+use ou_bench_unified::rng::RngKind;
+use ou_bench_unified::sched::Priority;
+use ou_bench_unified::stats::{Outliers, Summary};
+use ou_bench_unified::stop::{Adaptive, AutoWarmup, Warmup};
+use ou_bench_unified::sweep::{self, SweepPoint};
+use ou_bench_unified::verify::{self, Check};
+
+#[derive(Debug, Clone)]
 struct Args {
-    n: usize,
-    runs: usize,
-    warmup: usize,
-    seed: u32,
-    mode: Mode,
+    config: Config,
     output: Output,
-}
-
-#[derive(Debug, Clone, Copy)]
-enum Mode {
-    Full,
-    Gn,
-    Ou,
+    samples: Option<(String, SampleFormat)>,
+    sweep_n: Option<Vec<usize>>,
+    baseline: Option<String>,
+    /// Fraction, e.g. 0.05.
+    fail_threshold: f64,
+    verify: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -125,12 +40,13 @@ enum Output {
 
 fn parse_args() -> Args {
     let mut out = Args {
-        n: 500_000,
-        runs: 1000,
-        warmup: 5,
-        seed: 1,
-        mode: Mode::Full,
+        config: Config::default(),
         output: Output::Text,
+        samples: None,
+        sweep_n: None,
+        baseline: None,
+        fail_threshold: 0.05,
+        verify: false,
     };
 
     for arg in env::args().skip(1) {
@@ -146,29 +62,172 @@ fn parse_args() -> Args {
             "n" => {
                 let n: usize = v.parse().expect("--n must be an integer");
                 assert!(n >= 2, "--n must be >= 2");
-                out.n = n;
+                out.config.n = n;
+            }
+            "sweep-n" => {
+                let ns = sweep::parse_sweep_spec(v).expect(
+                    "--sweep-n must be a list like 1000,10000 or a range like 1000:1000000:10",
+                );
+                assert!(ns.iter().all(|&n| n >= 2), "--sweep-n values must be >= 2");
+                out.sweep_n = Some(ns);
+            }
+            "paths" => {
+                let paths: usize = v.parse().expect("--paths must be an integer");
+                assert!(paths >= 1, "--paths must be >= 1");
+                out.config.paths = paths;
+            }
+            "threads" => {
+                let threads: usize = v.parse().expect("--threads must be an integer");
+                assert!(threads >= 1, "--threads must be >= 1");
+                out.config.threads = threads;
             }
             "runs" => {
                 let runs: usize = v.parse().expect("--runs must be an integer");
                 assert!(runs >= 1, "--runs must be >= 1");
-                out.runs = runs;
+                out.config.runs = runs;
+            }
+            "target-rel-ci" => {
+                let target: f64 = v.parse().expect("--target-rel-ci must be a number");
+                assert!(target > 0.0, "--target-rel-ci must be > 0");
+                out.config
+                    .adaptive
+                    .get_or_insert_with(Adaptive::default)
+                    .target_rel_ci = Some(target);
+            }
+            "time-budget" => {
+                let budget =
+                    parse_duration(v).expect("--time-budget must look like 10s, 500ms or 2m");
+                out.config
+                    .adaptive
+                    .get_or_insert_with(Adaptive::default)
+                    .time_budget = Some(budget);
+            }
+            "max-runs" => {
+                let max_runs: usize = v.parse().expect("--max-runs must be an integer");
+                assert!(max_runs >= 1, "--max-runs must be >= 1");
+                out.config
+                    .adaptive
+                    .get_or_insert_with(Adaptive::default)
+                    .max_runs = max_runs;
             }
             "warmup" => {
-                let warmup: usize = v.parse().expect("--warmup must be an integer");
-                out.warmup = warmup;
+                out.config.warmup = if v == "auto" {
+                    Warmup::Auto(AutoWarmup::default())
+                } else {
+                    let warmup: usize = v.parse().expect("--warmup must be an integer or auto");
+                    Warmup::Fixed(warmup)
+                };
             }
             "seed" => {
                 let seed_u64: u64 = v.parse().expect("--seed must be an integer");
-                out.seed = (seed_u64 & 0xFFFF_FFFF) as u32;
+                out.config.seed = (seed_u64 & 0xFFFF_FFFF) as u32;
             }
             "mode" => {
-                out.mode = match v {
+                out.config.mode = match v {
                     "full" => Mode::Full,
                     "gn" => Mode::Gn,
                     "ou" => Mode::Ou,
                     _ => panic!("--mode must be full|gn|ou"),
                 };
             }
+            "rng" => {
+                out.config.rng = match v {
+                    "xorshift128" => RngKind::XorShift128,
+                    "splitmix32" => RngKind::SplitMix32,
+                    "pcg32" => RngKind::Pcg32,
+                    "xoshiro256ss" => RngKind::Xoshiro256StarStar,
+                    "wyrand" => RngKind::WyRand,
+                    _ => panic!("--rng must be xorshift128|splitmix32|pcg32|xoshiro256ss|wyrand"),
+                };
+            }
+            "normal" => {
+                out.config.normal = match v {
+                    "polar" => NormalKind::Polar,
+                    "ziggurat" => NormalKind::Ziggurat,
+                    "invcdf" => NormalKind::InvCdf,
+                    "boxmuller" => NormalKind::BoxMuller,
+                    _ => panic!("--normal must be ziggurat|polar|boxmuller|invcdf"),
+                };
+            }
+            "clock" => {
+                out.config.clock = match v {
+                    "instant" => ClockKind::Instant,
+                    "tsc" => ClockKind::Tsc,
+                    "thread-cputime" => ClockKind::ThreadCpu,
+                    "process-cputime" => ClockKind::ProcessCpu,
+                    _ => panic!("--clock must be instant|tsc|thread-cputime|process-cputime"),
+                };
+            }
+            "scheme" => {
+                out.config.scheme = match v {
+                    "euler" => Scheme::Euler,
+                    "exact" => Scheme::Exact,
+                    _ => panic!("--scheme must be euler|exact"),
+                };
+            }
+            "t" => {
+                let t: f64 = v.parse().expect("--t must be a number");
+                assert!(t.is_finite() && t > 0.0, "--t must be > 0");
+                out.config.params.t = t;
+            }
+            "theta" => {
+                let theta: f64 = v.parse().expect("--theta must be a number");
+                assert!(theta.is_finite() && theta >= 0.0, "--theta must be >= 0");
+                out.config.params.theta = theta;
+            }
+            "mu" => {
+                let mu: f64 = v.parse().expect("--mu must be a number");
+                assert!(mu.is_finite(), "--mu must be finite");
+                out.config.params.mu = mu;
+            }
+            "sigma" => {
+                let sigma: f64 = v.parse().expect("--sigma must be a number");
+                assert!(sigma.is_finite() && sigma >= 0.0, "--sigma must be >= 0");
+                out.config.params.sigma = sigma;
+            }
+            "x0" => {
+                let x0: f64 = v.parse().expect("--x0 must be a number");
+                assert!(x0.is_finite(), "--x0 must be finite");
+                out.config.params.x0 = x0;
+            }
+            "subtract-timer-overhead" => {
+                out.config.subtract_timer_overhead = true;
+            }
+            "pin-cpu" => {
+                out.config.pin_cpu = Some(v.parse().expect("--pin-cpu must be a core index"));
+            }
+            "priority" => {
+                out.config.priority = Some(match v {
+                    "normal" => Priority::Normal,
+                    "fifo" => Priority::Fifo,
+                    _ => panic!("--priority must be fifo|normal"),
+                });
+            }
+            "perf-counters" => {
+                out.config.perf_counters = true;
+            }
+            "reject-outliers" => {
+                out.config.reject_outliers = true;
+            }
+            "baseline" => {
+                out.baseline = Some(v.to_string());
+            }
+            "fail-threshold" => {
+                let pct: f64 = v
+                    .trim_end_matches('%')
+                    .parse()
+                    .expect("--fail-threshold must be a percentage like 5%");
+                assert!(pct >= 0.0, "--fail-threshold must be >= 0");
+                out.fail_threshold = pct / 100.0;
+            }
+            "verify" => {
+                out.verify = true;
+            }
+            "samples" => {
+                let format = SampleFormat::from_path(v)
+                    .expect("--samples path must end in .csv, .ndjson or .jsonl");
+                out.samples = Some((v.to_string(), format));
+            }
             "output" => {
                 out.output = match v {
                     "text" => Output::Text,
@@ -180,241 +239,707 @@ fn parse_args() -> Args {
         }
     }
 
+    // --max-runs only caps an adaptive count; it never starts one.
+    if let Some(ad) = &out.config.adaptive {
+        assert!(
+            ad.target_rel_ci.is_some() || ad.time_budget.is_some(),
+            "--max-runs requires --target-rel-ci or --time-budget"
+        );
+    }
+
+    // Thread 0's CPU clock stops while it waits at the phase barriers, so
+    // it would time only its own share of the work.
+    assert!(
+        out.config.clock != ClockKind::ThreadCpu || out.config.threads == 1,
+        "--clock=thread-cputime cannot be combined with --threads > 1"
+    );
+
+    // Workers inherit the main thread's affinity, so all of them would
+    // share the one core.
+    assert!(
+        out.config.pin_cpu.is_none() || out.config.threads == 1,
+        "--pin-cpu cannot be combined with --threads > 1"
+    );
+
     out
 }
 
+/// Parses `10s`, `500ms`, `2m` or a bare number of seconds.
+fn parse_duration(v: &str) -> Option<Duration> {
+    let (num, scale) = if let Some(x) = v.strip_suffix("ms") {
+        (x, 1e-3)
+    } else if let Some(x) = v.strip_suffix('s') {
+        (x, 1.0)
+    } else if let Some(x) = v.strip_suffix('m') {
+        (x, 60.0)
+    } else {
+        (v, 1.0)
+    };
+    let secs: f64 = num.parse().ok()?;
+    Duration::try_from_secs_f64(secs * scale).ok()
+}
+
 fn main() {
     let args = parse_args();
+    let cfg = args.config;
+
+    if let Some(ns) = &args.sweep_n {
+        assert!(
+            args.samples.is_none(),
+            "--samples cannot be combined with --sweep-n"
+        );
+        assert!(
+            args.baseline.is_none(),
+            "--baseline cannot be combined with --sweep-n"
+        );
+        assert!(!args.verify, "--verify cannot be combined with --sweep-n");
+        run_sweep(&args, ns);
+        return;
+    }
 
-    let t = 1.0_f64;
-    let theta = 1.0_f64;
-    let mu = 0.0_f64;
-    let sigma = 0.1_f64;
-
-    let n = args.n;
+    // Load the baseline first so a bad file fails before the runs.
+    let baseline = args.baseline.as_ref().map(|path| {
+        let text = fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("--baseline: cannot read {}: {}", path, e));
+        let record = compare::find_baseline(&text, &cfg)
+            .unwrap_or_else(|e| panic!("--baseline: {}: {}", path, e));
+        // A verdict across different configs would be meaningless, so this
+        // is an error with its own exit code rather than a regression.
+        let mismatches = compare::config_mismatches(&record, &cfg);
+        if !mismatches.is_empty() {
+            for m in &mismatches {
+                eprintln!("error: --baseline config differs, {}", m);
+            }
+            process::exit(2);
+        }
+        (path, record)
+    });
 
-    let dt = t / (n as f64);
-    let a = 1.0 - theta * dt;
-    let b = theta * mu * dt;
-    let diff = sigma * dt.sqrt();
+    let report = bench::run(&cfg);
 
-    let mut gn = vec![0.0_f64; n - 1];
-    let mut ou = vec![0.0_f64; n];
+    if let Some((path, format)) = &args.samples {
+        File::create(path)
+            .map(BufWriter::new)
+            .and_then(|mut w| export::write_samples(&mut w, *format, &report.samples))
+            .unwrap_or_else(|e| panic!("--samples: cannot write {}: {}", path, e));
+    }
 
-    if let Mode::Ou = args.mode {
-        let mut rng_prefill = XorShift128::new(args.seed);
-        let mut norm_prefill = NormalPolar::new();
-        for i in 0..(n - 1) {
-            gn[i] = diff * norm_prefill.next_standard(&mut rng_prefill);
-        }
+    match args.output {
+        Output::Json => print_json(&cfg, &report),
+        Output::Text => print_text(&cfg, &report),
     }
 
-    // Warmup
-    {
-        let mut rng = XorShift128::new(args.seed);
-        let mut norm = NormalPolar::new();
-        for _ in 0..args.warmup {
-            let mut s = 0.0_f64;
-            match args.mode {
-                Mode::Full => {
-                    for i in 0..(n - 1) {
-                        gn[i] = diff * norm.next_standard(&mut rng);
-                    }
-
-                    let mut x = 0.0_f64;
-                    ou[0] = x;
-                    for i in 1..n {
-                        x = a * x + b + gn[i - 1];
-                        ou[i] = x;
-                    }
-
-                    for v in &ou {
-                        s += *v;
-                    }
-                }
-                Mode::Gn => {
-                    for i in 0..(n - 1) {
-                        gn[i] = diff * norm.next_standard(&mut rng);
-                    }
-                    for v in &gn {
-                        s += *v;
-                    }
-                }
-                Mode::Ou => {
-                    let mut x = 0.0_f64;
-                    ou[0] = x;
-                    for i in 1..n {
-                        x = a * x + b + gn[i - 1];
-                        ou[i] = x;
-                    }
-
-                    for v in &ou {
-                        s += *v;
-                    }
-                }
-            }
-            if s == 123456789.0 {
-                eprintln!("impossible");
-            }
-        }
+    let mut failed = false;
+    if args.verify {
+        failed |= !verify(&cfg, &report, args.output);
     }
 
-    // Timed runs
-    let mut rng = XorShift128::new(args.seed);
-    let mut norm = NormalPolar::new();
-
-    let mut total_s = 0.0_f64;
-    let mut total_gen_s = 0.0_f64;
-    let mut total_sim_s = 0.0_f64;
-    let mut total_chk_s = 0.0_f64;
-
-    let mut min_s = f64::INFINITY;
-    let mut max_s = 0.0_f64;
-    let mut run_times: Vec<f64> = Vec::with_capacity(args.runs);
-
-    let mut checksum = 0.0_f64;
-
-    for _ in 0..args.runs {
-        let (gen, sim, chk, run);
-        match args.mode {
-            Mode::Full => {
-                let t0 = Instant::now();
-                for i in 0..(n - 1) {
-                    gn[i] = diff * norm.next_standard(&mut rng);
-                }
-                let t1 = Instant::now();
-
-                let mut x = 0.0_f64;
-                ou[0] = x;
-                for i in 1..n {
-                    x = a * x + b + gn[i - 1];
-                    ou[i] = x;
-                }
-                let t2 = Instant::now();
-
-                let mut s = 0.0_f64;
-                for v in &ou {
-                    s += *v;
-                }
-                checksum += s;
-                let t3 = Instant::now();
-
-                gen = t1.duration_since(t0).as_secs_f64();
-                sim = t2.duration_since(t1).as_secs_f64();
-                chk = t3.duration_since(t2).as_secs_f64();
-                run = t3.duration_since(t0).as_secs_f64();
-            }
-            Mode::Gn => {
-                let t0 = Instant::now();
-                for i in 0..(n - 1) {
-                    gn[i] = diff * norm.next_standard(&mut rng);
-                }
-                let t1 = Instant::now();
-
-                let mut s = 0.0_f64;
-                for v in &gn {
-                    s += *v;
-                }
-                checksum += s;
-                let t2 = Instant::now();
-
-                gen = t1.duration_since(t0).as_secs_f64();
-                sim = 0.0_f64;
-                chk = t2.duration_since(t1).as_secs_f64();
-                run = t2.duration_since(t0).as_secs_f64();
-            }
-            Mode::Ou => {
-                let t0 = Instant::now();
-                let mut x = 0.0_f64;
-                ou[0] = x;
-                for i in 1..n {
-                    x = a * x + b + gn[i - 1];
-                    ou[i] = x;
-                }
-                let t1 = Instant::now();
-
-                let mut s = 0.0_f64;
-                for v in &ou {
-                    s += *v;
-                }
-                checksum += s;
-                let t2 = Instant::now();
-
-                gen = 0.0_f64;
-                sim = t1.duration_since(t0).as_secs_f64();
-                chk = t2.duration_since(t1).as_secs_f64();
-                run = t2.duration_since(t0).as_secs_f64();
-            }
+    if let Some((path, record)) = baseline {
+        let comparisons = compare::compare(&record, &report, args.fail_threshold);
+        match args.output {
+            Output::Json => print_comparison_json(path, args.fail_threshold, &comparisons),
+            Output::Text => print_comparison_text(path, args.fail_threshold, &comparisons),
         }
+        failed |= comparisons.iter().any(|c| c.failed);
+    }
+    if failed {
+        process::exit(1);
+    }
+}
 
-        total_gen_s += gen;
-        total_sim_s += sim;
-        total_chk_s += chk;
-        total_s += run;
-        run_times.push(run);
+/// Checks the checksum and the reference prefix values; false on any
+/// mismatch, when this target has no reference to check against, or when
+/// there is no golden checksum for this configuration.
+fn verify(cfg: &Config, report: &Report, output: Output) -> bool {
+    let target = environment::BUILD.target;
+    let reference = verify::reference();
+    let expected = reference.and_then(|r| verify::golden_checksum(r, cfg, report.run_times.len()));
+    let mut checks = Vec::new();
+    if let Some(expected) = expected {
+        checks.push(Check {
+            name: "checksum".to_string(),
+            expected,
+            actual: report.checksum.to_bits(),
+        });
+    }
+    if let Some(prefix) = reference.and_then(|r| r.prefix.as_ref()) {
+        checks.extend(verify::check_prefix(prefix));
+    }
+    let mismatches: Vec<&Check> = checks.iter().filter(|c| !c.ok()).collect();
+    // A prefix-only pass says nothing about this run, so it is not "passed".
+    let status = if checks.is_empty() {
+        "unverified"
+    } else if !mismatches.is_empty() {
+        "failed"
+    } else if expected.is_none() {
+        "checksum_unchecked"
+    } else {
+        "passed"
+    };
 
-        if run < min_s {
-            min_s = run;
+    match output {
+        Output::Json => {
+            let mut out = JsonObject::new();
+            out.str("verify", status)
+                .str("target", target)
+                .bool("checksum_checked", expected.is_some())
+                .int("checks", checks.len() as u64)
+                .arr(
+                    "mismatches",
+                    mismatches
+                        .iter()
+                        .map(|c| {
+                            let mut o = JsonObject::new();
+                            o.str("name", &c.name)
+                                .str("expected", &format!("0x{:016X}", c.expected))
+                                .str("actual", &format!("0x{:016X}", c.actual));
+                            o
+                        })
+                        .collect(),
+                );
+            println!("{}", out.finish());
         }
-        if run > max_s {
-            max_s = run;
+        Output::Text => {
+            println!();
+            println!("== verify (target {}) ==", target);
+            match expected {
+                Some(e) => println!(
+                    "checksum expected={:.17} actual={:.17}",
+                    f64::from_bits(e),
+                    report.checksum
+                ),
+                None => println!("checksum: no golden value for this configuration on this target"),
+            }
+            for c in &mismatches {
+                println!(
+                    "MISMATCH {} expected={:e} (0x{:016X}) actual={:e} (0x{:016X})",
+                    c.name,
+                    f64::from_bits(c.expected),
+                    c.expected,
+                    f64::from_bits(c.actual),
+                    c.actual
+                );
+            }
+            println!(
+                "verify={} checks={} mismatches={}",
+                status,
+                checks.len(),
+                mismatches.len()
+            );
         }
     }
+    // Stderr, so a CI gate notices even when stdout is parsed as JSON.
+    if checks.is_empty() {
+        eprintln!("verify: no reference values for target {}", target);
+    } else if expected.is_none() {
+        eprintln!("verify: checksum not checked, no golden value for this configuration");
+    }
+    status == "passed"
+}
 
-    run_times.sort_by(|a, b| a.partial_cmp(b).unwrap());
-    let median_s = if args.runs % 2 == 1 {
-        run_times[args.runs / 2]
-    } else {
-        (run_times[args.runs / 2 - 1] + run_times[args.runs / 2]) / 2.0
-    };
+fn print_comparison_json(path: &str, threshold: f64, comparisons: &[Comparison]) {
+    let metrics = comparisons
+        .iter()
+        .map(|c| {
+            let mut o = JsonObject::new();
+            o.str("metric", c.metric)
+                .fixed("baseline_ms", c.baseline.median * 1000.0, 6)
+                .fixed("current_ms", c.current.median * 1000.0, 6)
+                .fixed("change_pct", c.change * 100.0, 2)
+                .str("verdict", c.verdict.as_str())
+                .bool("failed", c.failed);
+            o
+        })
+        .collect();
+    let mut out = JsonObject::new();
+    out.str("baseline", path)
+        .fixed("fail_threshold_pct", threshold * 100.0, 2)
+        .arr("metrics", metrics)
+        .str("verdict", compare::overall(comparisons).as_str())
+        .bool("failed", comparisons.iter().any(|c| c.failed));
+    println!("{}", out.finish());
+}
 
-    let avg_ms = (total_s / args.runs as f64) * 1000.0;
-    let median_ms = median_s * 1000.0;
-    let min_ms = min_s * 1000.0;
-    let max_ms = max_s * 1000.0;
+fn print_comparison_text(path: &str, threshold: f64, comparisons: &[Comparison]) {
+    println!();
+    println!(
+        "== baseline {} (fail_threshold={:.2}%) ==",
+        path,
+        threshold * 100.0
+    );
+    for c in comparisons {
+        println!(
+            "{:<12} {:>12.6} -> {:>12.6} ms {:>+8.2}% {}{}",
+            c.metric,
+            c.baseline.median * 1000.0,
+            c.current.median * 1000.0,
+            c.change * 100.0,
+            c.verdict.as_str(),
+            if c.failed { " FAIL" } else { "" }
+        );
+    }
+    println!(
+        "verdict={} failed={}",
+        compare::overall(comparisons).as_str(),
+        comparisons.iter().any(|c| c.failed)
+    );
+}
 
-    let mode_str = match args.mode {
-        Mode::Full => "full",
-        Mode::Gn => "gn",
-        Mode::Ou => "ou",
-    };
+fn run_sweep(args: &Args, ns: &[usize]) {
+    let results = sweep::run_sweep(&args.config, ns);
+    let points: Vec<SweepPoint> = results
+        .iter()
+        .map(|(cfg, report)| SweepPoint::new(cfg, report))
+        .collect();
+    let fit = sweep::fit_median(&points);
 
     match args.output {
         Output::Json => {
-            println!(
-                r#"{{"language":"Rust","mode":"{}","n":{},"runs":{},"warmup":{},"seed":{},"total_s":{:.6},"avg_ms":{:.6},"median_ms":{:.6},"min_ms":{:.6},"max_ms":{:.6},"breakdown_s":{{"gen_normals":{:.6},"simulate":{:.6},"checksum":{:.6}}},"checksum":{:.17}}}"#,
-                mode_str,
-                args.n,
-                args.runs,
-                args.warmup,
-                args.seed,
-                total_s,
-                avg_ms,
-                median_ms,
-                min_ms,
-                max_ms,
-                total_gen_s,
-                total_sim_s,
-                total_chk_s,
-                checksum
-            );
+            for (cfg, report) in &results {
+                print_json(cfg, report);
+            }
+            let mut out = JsonObject::new();
+            out.str("language", "Rust")
+                .str("mode", args.config.mode.as_str())
+                .arr(
+                    "sweep",
+                    points
+                        .iter()
+                        .map(|p| {
+                            let mut o = JsonObject::new();
+                            o.int("n", p.n as u64)
+                                .fixed("median_ms", p.median_s * 1000.0, 6)
+                                .fixed("gen_normals_ns_per_step", p.gen_ns_per_step, 4)
+                                .fixed("simulate_ns_per_step", p.sim_ns_per_step, 4)
+                                .fixed("checksum_ns_per_step", p.chk_ns_per_step, 4);
+                            o
+                        })
+                        .collect(),
+                );
+            let mut f = JsonObject::new();
+            f.fixed("ns_per_step", fit.slope * 1e9, 4)
+                .fixed("intercept_ms", fit.intercept * 1000.0, 6)
+                .fixed("r2", fit.r2, 6);
+            out.obj("fit", f);
+            println!("{}", out.finish());
         }
         Output::Text => {
-            println!("== OU benchmark (Rust, unified algorithms) ==");
-            println!(
-                "n={} runs={} warmup={} seed={}",
-                args.n, args.runs, args.warmup, args.seed
-            );
-            println!("total_s={:.6}", total_s);
+            for (cfg, report) in &results {
+                print_text(cfg, report);
+                println!();
+            }
+            println!("== sweep over n (median_ms = a*n + b) ==");
             println!(
-                "avg_ms={:.6} median_ms={:.6} min_ms={:.6} max_ms={:.6}",
-                avg_ms, median_ms, min_ms, max_ms
+                "{:>12} {:>14} {:>14} {:>14} {:>14}",
+                "n", "median_ms", "gen_ns/step", "sim_ns/step", "chk_ns/step"
             );
+            for p in &points {
+                println!(
+                    "{:>12} {:>14.6} {:>14.4} {:>14.4} {:>14.4}",
+                    p.n,
+                    p.median_s * 1000.0,
+                    p.gen_ns_per_step,
+                    p.sim_ns_per_step,
+                    p.chk_ns_per_step
+                );
+            }
             println!(
-                "breakdown_s gen_normals={:.6} simulate={:.6} checksum={:.6}",
-                total_gen_s, total_sim_s, total_chk_s
+                "fit a_ns_per_step={:.4} b_ms={:.6} r2={:.6}",
+                fit.slope * 1e9,
+                fit.intercept * 1000.0,
+                fit.r2
             );
-            println!("checksum={:.17}", checksum);
         }
     }
 }
+
+fn print_json(cfg: &Config, report: &Report) {
+    let p = cfg.params;
+
+    let mut breakdown = JsonObject::new();
+    breakdown
+        .fixed("gen_normals", report.total_gen_s, 6)
+        .fixed("simulate", report.total_sim_s, 6)
+        .fixed("checksum", report.total_chk_s, 6);
+
+    let tp = Throughput::new(cfg, report);
+    let mut throughput = JsonObject::new();
+    throughput
+        .fixed("ns_per_normal", tp.ns_per_normal, 4)
+        .fixed("ns_per_ou_step", tp.ns_per_ou_step, 4)
+        .fixed("normals_per_s", tp.normals_per_s, 0)
+        .fixed("simulate_gb_per_s", tp.sim_bytes_per_s / 1e9, 4)
+        .fixed("checksum_gb_per_s", tp.chk_bytes_per_s / 1e9, 4);
+
+    let mut out = JsonObject::new();
+    out.str("language", "Rust")
+        .str("mode", cfg.mode.as_str())
+        .int("n", cfg.n as u64)
+        .int("paths", cfg.paths as u64)
+        .int("threads", report.threads as u64)
+        .int("runs", report.run_times.len() as u64)
+        .str("stop", report.stop.as_str())
+        .int("warmup", report.warmup_runs as u64)
+        .int("seed", cfg.seed)
+        .str("rng", cfg.rng.as_str())
+        .str("normal", cfg.normal.as_str())
+        .str("scheme", cfg.scheme.as_str())
+        .num("t", p.t)
+        .num("theta", p.theta)
+        .num("mu", p.mu)
+        .num("sigma", p.sigma)
+        .num("x0", p.x0)
+        .obj("clock", clock_json(cfg.clock, report))
+        .obj("sched", sched_json(cfg, report))
+        .fixed("total_s", report.total_s, 6)
+        .fixed("avg_ms", report.avg_s() * 1000.0, 6)
+        .fixed("median_ms", report.median_s * 1000.0, 6)
+        .fixed("min_ms", report.min_s * 1000.0, 6)
+        .fixed("max_ms", report.max_s * 1000.0, 6)
+        .obj("stats_ms", stats_json(&report.summary, 1000.0))
+        .obj("outliers", outliers_json(&report.outliers))
+        .obj("breakdown_s", breakdown)
+        .obj("phases_ms", phases_json(report));
+    match &report.perf {
+        Some(Ok(pc)) => {
+            let mut counters = JsonObject::new();
+            counters
+                .bool("available", true)
+                .fixed("running_fraction", pc.running_fraction, 4)
+                .obj("gen_normals", counts_json(&pc.gen))
+                .obj("simulate", counts_json(&pc.sim))
+                .obj("checksum", counts_json(&pc.chk));
+            out.obj("perf_counters", counters);
+        }
+        Some(Err(e)) => {
+            let mut counters = JsonObject::new();
+            counters
+                .bool("available", false)
+                .str("error", &e.to_string());
+            out.obj("perf_counters", counters);
+        }
+        None => {
+            out.null("perf_counters");
+        }
+    }
+    out.obj("throughput", throughput)
+        .fixed("checksum", report.checksum, 17);
+    match report.warmup_converged {
+        Some(c) => {
+            out.bool("warmup_converged", c);
+        }
+        None => {
+            out.null("warmup_converged");
+        }
+    }
+    match report.scaling {
+        Some(sc) => {
+            let mut scaling = JsonObject::new();
+            scaling
+                .int("serial_runs", sc.serial_runs as u64)
+                .fixed("serial_median_ms", sc.serial_median_s * 1000.0, 6)
+                .fixed("speedup", sc.speedup, 4)
+                .fixed("efficiency", sc.efficiency, 4)
+                .fixed("gen_normals", sc.gen_speedup, 4)
+                .fixed("simulate", sc.sim_speedup, 4)
+                .fixed("checksum", sc.chk_speedup, 4);
+            out.obj("scaling", scaling);
+        }
+        None => {
+            out.null("scaling");
+        }
+    }
+    match report.terminal {
+        Some(ts) => {
+            let mut terminal = JsonObject::new();
+            terminal
+                .num("mean", ts.mean)
+                .num("variance", ts.variance)
+                .num("time", p.terminal_time(cfg.n))
+                .num("expected_mean", p.terminal_mean(cfg.n))
+                .num("expected_variance", p.terminal_variance(cfg.n));
+            out.obj("terminal", terminal);
+        }
+        None => {
+            out.null("terminal");
+        }
+    }
+    out.obj("environment", environment_json(&Environment::capture()));
+    println!("{}", out.finish());
+}
+
+fn phases_json(report: &Report) -> JsonObject {
+    let mut o = JsonObject::new();
+    for (name, s) in compare::METRICS[1..].iter().zip(&report.phases) {
+        let mut p = JsonObject::new();
+        p.fixed("median", s.median * 1000.0, 6)
+            .fixed("ci95_lo", s.median_ci95.0 * 1000.0, 6)
+            .fixed("ci95_hi", s.median_ci95.1 * 1000.0, 6);
+        o.obj(name, p);
+    }
+    o
+}
+
+fn clock_json(kind: ClockKind, report: &Report) -> JsonObject {
+    let c = &report.clock;
+    let mut o = JsonObject::new();
+    o.str("name", kind.as_str())
+        .fixed("ticks_per_s", c.ticks_per_s, 0)
+        .fixed("overhead_ns", c.overhead_s * 1e9, 3)
+        .fixed("resolution_ns", c.resolution_s * 1e9, 3)
+        .bool("overhead_subtracted", report.overhead_subtracted);
+    o
+}
+
+fn environment_json(e: &Environment) -> JsonObject {
+    let opt_str = |o: &mut JsonObject, k: &str, v: &Option<String>| {
+        match v {
+            Some(v) => o.str(k, v),
+            None => o.null(k),
+        };
+    };
+    let opt_int = |o: &mut JsonObject, k: &str, v: Option<usize>| {
+        match v {
+            Some(v) => o.int(k, v as u64),
+            None => o.null(k),
+        };
+    };
+    let b = &e.build;
+    let mut build = JsonObject::new();
+    build
+        .str("rustc", b.rustc)
+        .str("target", b.target)
+        .str("target_cpu", b.target_cpu)
+        .str("target_features", b.target_features)
+        .str("profile", b.profile)
+        .str("opt_level", b.opt_level)
+        .str("lto", b.lto)
+        .str("codegen_units", b.codegen_units)
+        .str("panic", b.panic);
+
+    let mut o = JsonObject::new();
+    o.str("timestamp", &e.timestamp)
+        .str("os", e.os)
+        .str("arch", e.arch);
+    opt_str(&mut o, "kernel", &e.kernel);
+    opt_str(&mut o, "cpu_model", &e.cpu_model);
+    opt_int(&mut o, "logical_cpus", e.logical_cpus);
+    opt_int(&mut o, "available_cpus", e.available_cpus);
+    opt_str(&mut o, "governor", &e.governor);
+    o.obj("build", build);
+    o
+}
+
+fn sched_json(cfg: &Config, report: &Report) -> JsonObject {
+    let mut o = JsonObject::new();
+    match cfg.pin_cpu {
+        Some(cpu) => o.int("pin_cpu", cpu as u64),
+        None => o.null("pin_cpu"),
+    };
+    match cfg.priority {
+        Some(p) => o.str("priority", p.as_str()),
+        None => o.null("priority"),
+    };
+    let cpus: Vec<String> = report.cpus.cpus.iter().map(|c| c.to_string()).collect();
+    o.raw("cpus", &format!("[{}]", cpus.join(",")))
+        .int("migrations", report.cpus.migrations as u64);
+    o
+}
+
+fn counts_json(c: &EventCounts) -> JsonObject {
+    let mut o = JsonObject::new();
+    for (k, name) in perf::EVENTS.iter().enumerate() {
+        match c.get(k) {
+            Some(v) => o.int(name, v),
+            None => o.null(name),
+        };
+    }
+    match c.ipc() {
+        Some(ipc) => o.fixed("ipc", ipc, 4),
+        None => o.null("ipc"),
+    };
+    o
+}
+
+fn counts_text(c: &EventCounts) -> String {
+    let mut line = String::new();
+    for (k, name) in perf::EVENTS.iter().enumerate() {
+        match c.get(k) {
+            Some(v) => line.push_str(&format!(" {}={}", name, v)),
+            None => line.push_str(&format!(" {}=n/a", name)),
+        }
+    }
+    match c.ipc() {
+        Some(ipc) => line.push_str(&format!(" ipc={:.4}", ipc)),
+        None => line.push_str(" ipc=n/a"),
+    }
+    line
+}
+
+fn stats_json(s: &Summary, scale: f64) -> JsonObject {
+    let mut o = JsonObject::new();
+    o.fixed("stddev", s.stddev * scale, 6)
+        .fixed("cv", s.cv, 6)
+        .fixed("p5", s.p5 * scale, 6)
+        .fixed("p25", s.p25 * scale, 6)
+        .fixed("p75", s.p75 * scale, 6)
+        .fixed("p95", s.p95 * scale, 6)
+        .fixed("p99", s.p99 * scale, 6)
+        .fixed("mad", s.mad * scale, 6)
+        .fixed("median_ci95_lo", s.median_ci95.0 * scale, 6)
+        .fixed("median_ci95_hi", s.median_ci95.1 * scale, 6);
+    o
+}
+
+fn outliers_json(o: &Outliers) -> JsonObject {
+    let mut j = JsonObject::new();
+    j.int("low_severe", o.low_severe as u64)
+        .int("low_mild", o.low_mild as u64)
+        .int("high_mild", o.high_mild as u64)
+        .int("high_severe", o.high_severe as u64)
+        .int("rejected", o.rejected as u64);
+    j
+}
+
+fn print_stats_text(s: &Summary) {
+    println!(
+        "stddev_ms={:.6} cv={:.6} mad_ms={:.6} median_ci95_ms=[{:.6}, {:.6}]",
+        s.stddev * 1000.0,
+        s.cv,
+        s.mad * 1000.0,
+        s.median_ci95.0 * 1000.0,
+        s.median_ci95.1 * 1000.0
+    );
+    println!(
+        "p5_ms={:.6} p25_ms={:.6} p75_ms={:.6} p95_ms={:.6} p99_ms={:.6}",
+        s.p5 * 1000.0,
+        s.p25 * 1000.0,
+        s.p75 * 1000.0,
+        s.p95 * 1000.0,
+        s.p99 * 1000.0
+    );
+}
+
+fn print_text(cfg: &Config, report: &Report) {
+    let p = cfg.params;
+
+    println!("== OU benchmark (Rust, unified algorithms) ==");
+    println!(
+        "n={} runs={} warmup={} seed={} rng={} normal={}",
+        cfg.n,
+        report.run_times.len(),
+        report.warmup_runs,
+        cfg.seed,
+        cfg.rng.as_str(),
+        cfg.normal.as_str()
+    );
+    println!(
+        "scheme={} t={} theta={} mu={} sigma={} x0={}",
+        cfg.scheme.as_str(),
+        p.t,
+        p.theta,
+        p.mu,
+        p.sigma,
+        p.x0
+    );
+    if cfg.paths > 1 {
+        println!("paths={}", cfg.paths);
+    }
+    let cpus: Vec<String> = report.cpus.cpus.iter().map(|c| c.to_string()).collect();
+    println!(
+        "sched pin_cpu={} priority={} cpus=[{}] migrations={}",
+        cfg.pin_cpu.map_or("none".to_string(), |c| c.to_string()),
+        cfg.priority.map_or("unchanged", Priority::as_str),
+        cpus.join(","),
+        report.cpus.migrations
+    );
+    let c = &report.clock;
+    println!(
+        "clock={} ticks_per_s={:.0} overhead_ns={:.3} resolution_ns={:.3} overhead_subtracted={}",
+        cfg.clock.as_str(),
+        c.ticks_per_s,
+        c.overhead_s * 1e9,
+        c.resolution_s * 1e9,
+        report.overhead_subtracted
+    );
+    if let Some(converged) = report.warmup_converged {
+        println!(
+            "auto_warmup iterations={} converged={}",
+            report.warmup_runs, converged
+        );
+    }
+    if cfg.adaptive.is_some() {
+        println!(
+            "adaptive runs={} stop={}",
+            report.run_times.len(),
+            report.stop.as_str()
+        );
+    }
+    println!("total_s={:.6}", report.total_s);
+    println!(
+        "avg_ms={:.6} median_ms={:.6} min_ms={:.6} max_ms={:.6}",
+        report.avg_s() * 1000.0,
+        report.median_s * 1000.0,
+        report.min_s * 1000.0,
+        report.max_s * 1000.0
+    );
+    print_stats_text(&report.summary);
+    let o = &report.outliers;
+    println!(
+        "outliers low_severe={} low_mild={} high_mild={} high_severe={} rejected={}",
+        o.low_severe, o.low_mild, o.high_mild, o.high_severe, o.rejected
+    );
+    println!(
+        "breakdown_s gen_normals={:.6} simulate={:.6} checksum={:.6}",
+        report.total_gen_s, report.total_sim_s, report.total_chk_s
+    );
+    match &report.perf {
+        Some(Ok(pc)) => {
+            println!("perf gen_normals{}", counts_text(&pc.gen));
+            println!("perf simulate{}", counts_text(&pc.sim));
+            println!("perf checksum{}", counts_text(&pc.chk));
+            if pc.running_fraction < 1.0 {
+                println!(
+                    "perf multiplexed: group ran {:.1}% of the time, counts are scaled",
+                    pc.running_fraction * 100.0
+                );
+            }
+        }
+        Some(Err(e)) => println!("perf unavailable: {}", e),
+        None => {}
+    }
+    let tp = Throughput::new(cfg, report);
+    println!(
+        "throughput ns_per_normal={:.4} ns_per_ou_step={:.4} normals_per_s={:.0} simulate_gb_per_s={:.4} checksum_gb_per_s={:.4}",
+        tp.ns_per_normal,
+        tp.ns_per_ou_step,
+        tp.normals_per_s,
+        tp.sim_bytes_per_s / 1e9,
+        tp.chk_bytes_per_s / 1e9
+    );
+    if let Some(sc) = report.scaling {
+        println!(
+            "threads={} serial_runs={} serial_median_ms={:.6} speedup={:.4} efficiency={:.4}",
+            report.threads,
+            sc.serial_runs,
+            sc.serial_median_s * 1000.0,
+            sc.speedup,
+            sc.efficiency
+        );
+        println!(
+            "speedup gen_normals={:.4} simulate={:.4} checksum={:.4}",
+            sc.gen_speedup, sc.sim_speedup, sc.chk_speedup
+        );
+    }
+    println!("checksum={:.17}", report.checksum);
+    if let (Some(ts), true) = (report.terminal, cfg.paths > 1) {
+        println!(
+            "terminal t={:.6} mean={:.6} variance={:.6} expected_mean={:.6} expected_variance={:.6}",
+            p.terminal_time(cfg.n),
+            ts.mean,
+            ts.variance,
+            p.terminal_mean(cfg.n),
+            p.terminal_variance(cfg.n)
+        );
+    }
+}
@@ -0,0 +1,493 @@
+//! Standard normal samplers driven by the uniform generators in [`crate::rng`].
+//!
+//! [`NormalPolar`] is the sampler every language implementation uses; the
+//! others exist to measure how much of `gen_normals` the sampler accounts for.
+
+use std::sync::OnceLock;
+
+use crate::rng::UniformRng;
+
+/// Maps a uniform stream to standard normal variates.
+///
+/// Samplers are deterministic: the same generator state always produces the
+/// same output sequence.
+pub trait NormalSampler: Default {
+    fn next_standard<R: UniformRng>(&mut self, rng: &mut R) -> f64;
+}
+
+/// Sampler selected with `--normal`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalKind {
+    Polar,
+    Ziggurat,
+    InvCdf,
+    BoxMuller,
+}
+
+impl NormalKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NormalKind::Polar => "polar",
+            NormalKind::Ziggurat => "ziggurat",
+            NormalKind::InvCdf => "invcdf",
+            NormalKind::BoxMuller => "boxmuller",
+        }
+    }
+}
+
+/// Marsaglia polar method; every accepted pair yields one value now and
+/// caches the other as a spare for the next call.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NormalPolar {
+    has_spare: bool,
+    spare: f64,
+}
+
+impl NormalPolar {
+    pub fn new() -> Self {
+        Self {
+            has_spare: false,
+            spare: 0.0,
+        }
+    }
+
+    #[inline(always)]
+    pub fn next_standard<R: UniformRng>(&mut self, rng: &mut R) -> f64 {
+        if self.has_spare {
+            self.has_spare = false;
+            return self.spare;
+        }
+
+        loop {
+            let u = 2.0 * rng.next_f64() - 1.0;
+            let v = 2.0 * rng.next_f64() - 1.0;
+            let s = u * u + v * v;
+            if s > 0.0 && s < 1.0 {
+                let m = (-2.0 * s.ln() / s).sqrt();
+                self.spare = v * m;
+                self.has_spare = true;
+                return u * m;
+            }
+        }
+    }
+}
+
+impl NormalSampler for NormalPolar {
+    #[inline(always)]
+    fn next_standard<R: UniformRng>(&mut self, rng: &mut R) -> f64 {
+        NormalPolar::next_standard(self, rng)
+    }
+}
+
+/// Trigonometric Box-Muller; same spare caching as [`NormalPolar`], but
+/// exactly two `next_f64` draws per pair and no rejections.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BoxMuller {
+    has_spare: bool,
+    spare: f64,
+}
+
+impl BoxMuller {
+    pub fn new() -> Self {
+        Self {
+            has_spare: false,
+            spare: 0.0,
+        }
+    }
+
+    #[inline(always)]
+    pub fn next_standard<R: UniformRng>(&mut self, rng: &mut R) -> f64 {
+        if self.has_spare {
+            self.has_spare = false;
+            return self.spare;
+        }
+
+        // 1 - u keeps the log argument in (0,1].
+        let u1 = 1.0 - rng.next_f64();
+        let u2 = rng.next_f64();
+        let r = (-2.0 * u1.ln()).sqrt();
+        let (s, c) = (std::f64::consts::TAU * u2).sin_cos();
+        self.spare = r * s;
+        self.has_spare = true;
+        r * c
+    }
+}
+
+impl NormalSampler for BoxMuller {
+    #[inline(always)]
+    fn next_standard<R: UniformRng>(&mut self, rng: &mut R) -> f64 {
+        BoxMuller::next_standard(self, rng)
+    }
+}
+
+const ZIG_LAYERS: usize = 128;
+/// Start of the tail, `r` in Marsaglia and Tsang (2000).
+const ZIG_R: f64 = 3.442619855899;
+/// Area of each layer.
+const ZIG_V: f64 = 9.91256303526217e-3;
+const ZIG_M1: f64 = 2147483648.0; // 2^31
+
+#[derive(Debug)]
+struct ZigTables {
+    kn: [u32; ZIG_LAYERS],
+    wn: [f64; ZIG_LAYERS],
+    fn_: [f64; ZIG_LAYERS],
+}
+
+impl ZigTables {
+    fn build() -> Self {
+        let mut kn = [0_u32; ZIG_LAYERS];
+        let mut wn = [0.0_f64; ZIG_LAYERS];
+        let mut fn_ = [0.0_f64; ZIG_LAYERS];
+
+        let mut dn = ZIG_R;
+        let mut tn = dn;
+        let q = ZIG_V / (-0.5 * dn * dn).exp();
+
+        kn[0] = ((dn / q) * ZIG_M1) as u32;
+        kn[1] = 0;
+        wn[0] = q / ZIG_M1;
+        wn[ZIG_LAYERS - 1] = dn / ZIG_M1;
+        fn_[0] = 1.0;
+        fn_[ZIG_LAYERS - 1] = (-0.5 * dn * dn).exp();
+
+        for i in (1..ZIG_LAYERS - 1).rev() {
+            dn = (-2.0 * (ZIG_V / dn + (-0.5 * dn * dn).exp()).ln()).sqrt();
+            kn[i + 1] = ((dn / tn) * ZIG_M1) as u32;
+            tn = dn;
+            fn_[i] = (-0.5 * dn * dn).exp();
+            wn[i] = dn / ZIG_M1;
+        }
+
+        Self { kn, wn, fn_ }
+    }
+}
+
+static ZIG_TABLES: OnceLock<ZigTables> = OnceLock::new();
+
+/// Marsaglia-Tsang Ziggurat (RNOR) with 128 layers.
+///
+/// One `next_u32` draw picks the layer and the candidate; roughly 98.8% of
+/// calls return from that fast path without any transcendental function.
+/// The tables are built on first use and shared by every instance, so
+/// per-path samplers stay cheap.
+#[derive(Debug, Clone, Copy)]
+pub struct Ziggurat {
+    t: &'static ZigTables,
+}
+
+impl Ziggurat {
+    pub fn new() -> Self {
+        Self {
+            t: ZIG_TABLES.get_or_init(ZigTables::build),
+        }
+    }
+
+    #[inline(always)]
+    pub fn next_standard<R: UniformRng>(&mut self, rng: &mut R) -> f64 {
+        let hz = rng.next_u32() as i32;
+        let iz = (hz & (ZIG_LAYERS as i32 - 1)) as usize;
+        if hz.unsigned_abs() < self.t.kn[iz] {
+            return hz as f64 * self.t.wn[iz];
+        }
+        self.next_slow(rng, hz, iz)
+    }
+
+    #[cold]
+    fn next_slow<R: UniformRng>(&self, rng: &mut R, mut hz: i32, mut iz: usize) -> f64 {
+        let t = self.t;
+        loop {
+            let x = hz as f64 * t.wn[iz];
+
+            if iz == 0 {
+                // Base strip: sample the tail beyond r (Marsaglia 1964).
+                loop {
+                    let x = -(1.0 - rng.next_f64()).ln() / ZIG_R;
+                    let y = -(1.0 - rng.next_f64()).ln();
+                    if y + y >= x * x {
+                        return if hz > 0 { ZIG_R + x } else { -ZIG_R - x };
+                    }
+                }
+            }
+
+            let f = t.fn_[iz] + rng.next_f64() * (t.fn_[iz - 1] - t.fn_[iz]);
+            if f < (-0.5 * x * x).exp() {
+                return x;
+            }
+
+            hz = rng.next_u32() as i32;
+            iz = (hz & (ZIG_LAYERS as i32 - 1)) as usize;
+            if hz.unsigned_abs() < t.kn[iz] {
+                return hz as f64 * t.wn[iz];
+            }
+        }
+    }
+}
+
+impl Default for Ziggurat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NormalSampler for Ziggurat {
+    #[inline(always)]
+    fn next_standard<R: UniformRng>(&mut self, rng: &mut R) -> f64 {
+        Ziggurat::next_standard(self, rng)
+    }
+}
+
+/// Inverse-CDF sampler: one `next_f64` draw per value, mapped through
+/// [`inv_norm_cdf`]. No rejections and no cached state, so the i-th output
+/// depends only on the i-th uniform, as quasi-Monte Carlo and antithetic
+/// schemes require.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InvCdf;
+
+impl InvCdf {
+    pub fn new() -> Self {
+        Self
+    }
+
+    #[inline(always)]
+    pub fn next_standard<R: UniformRng>(&mut self, rng: &mut R) -> f64 {
+        // The uniform is k * 2^-53; use the midpoint (2k + 1) * 2^-54 of its
+        // cell so p lies in (0,1). Midpoints above 1/2 are not representable,
+        // so the upper half is mirrored onto the lower one, where they are
+        // exact; the grid is then symmetric about 1/2.
+        let k = (rng.next_f64() * 9007199254740992.0) as u64; // 2^53
+        let upper = k >= 1 << 52;
+        let m = if upper { (1 << 53) - 1 - k } else { k };
+        let z = inv_norm_cdf(((m << 1) | 1) as f64 * (1.0 / 18014398509481984.0)); // 2^-54
+        if upper {
+            -z
+        } else {
+            z
+        }
+    }
+}
+
+impl NormalSampler for InvCdf {
+    #[inline(always)]
+    fn next_standard<R: UniformRng>(&mut self, rng: &mut R) -> f64 {
+        InvCdf::next_standard(self, rng)
+    }
+}
+
+/// Standard normal quantile, Wichura's AS241 (PPND16); relative error about 1e-16.
+///
+/// `p` must lie in (0,1); the endpoints map to infinities. Coefficients are
+/// kept exactly as published.
+#[allow(clippy::excessive_precision)]
+#[inline(always)]
+pub fn inv_norm_cdf(p: f64) -> f64 {
+    let q = p - 0.5;
+    if q.abs() <= 0.425 {
+        let r = 0.180625 - q * q;
+        return q
+            * (((((((2.509_080_928_730_122_672_7e3 * r + 3.343_057_558_358_812_810_5e4) * r
+                + 6.726_577_092_700_870_085_3e4)
+                * r
+                + 4.592_195_393_154_987_145_7e4)
+                * r
+                + 1.373_169_376_550_946_112_5e4)
+                * r
+                + 1.971_590_950_306_551_442_7e3)
+                * r
+                + 1.331_416_678_917_843_774_5e2)
+                * r
+                + 3.387_132_872_796_366_608_0)
+            / (((((((5.226_495_278_852_854_561_0e3 * r + 2.872_908_573_572_194_267_4e4) * r
+                + 3.930_789_580_009_271_061_0e4)
+                * r
+                + 2.121_379_430_158_659_586_7e4)
+                * r
+                + 5.394_196_021_424_751_107_7e3)
+                * r
+                + 6.871_870_074_920_579_083_0e2)
+                * r
+                + 4.231_333_070_160_091_125_2e1)
+                * r
+                + 1.0);
+    }
+
+    let mut r = if q < 0.0 { p } else { 1.0 - p };
+    r = (-r.ln()).sqrt();
+    let val = if r <= 5.0 {
+        r -= 1.6;
+        (((((((7.745_450_142_783_414_076_4e-4 * r + 2.272_384_498_926_918_458_33e-2) * r
+            + 2.417_807_251_774_506_117_7e-1)
+            * r
+            + 1.270_458_252_452_368_382_58)
+            * r
+            + 3.647_848_324_763_204_605_04)
+            * r
+            + 5.769_497_221_460_691_405_5)
+            * r
+            + 4.630_337_846_156_545_295_9)
+            * r
+            + 1.423_437_110_749_683_577_34)
+            / (((((((1.050_750_071_644_416_843_24e-9 * r + 5.475_938_084_995_344_946e-4) * r
+                + 1.519_866_656_361_645_719_66e-2)
+                * r
+                + 1.481_039_764_274_800_745_9e-1)
+                * r
+                + 6.897_673_349_851_000_045_5e-1)
+                * r
+                + 1.676_384_830_183_803_849_4)
+                * r
+                + 2.053_191_626_637_758_821_87)
+                * r
+                + 1.0)
+    } else {
+        r -= 5.0;
+        (((((((2.010_334_399_292_288_132_65e-7 * r + 2.711_555_568_743_487_578_15e-5) * r
+            + 1.242_660_947_388_078_438_6e-3)
+            * r
+            + 2.653_218_952_657_612_309_3e-2)
+            * r
+            + 2.965_605_718_285_048_912_3e-1)
+            * r
+            + 1.784_826_539_917_291_335_8)
+            * r
+            + 5.463_784_911_164_114_369_9)
+            * r
+            + 6.657_904_643_501_103_777_2)
+            / (((((((2.044_263_103_389_939_785_64e-15 * r + 1.421_511_758_316_445_888_7e-7)
+                * r
+                + 1.846_318_317_510_054_681_8e-5)
+                * r
+                + 7.868_691_311_456_132_591e-4)
+                * r
+                + 1.487_536_129_085_061_485_25e-2)
+                * r
+                + 1.369_298_809_227_358_053_1e-1)
+                * r
+                + 5.998_322_065_558_879_376_9e-1)
+                * r
+                + 1.0)
+    };
+
+    if q < 0.0 {
+        -val
+    } else {
+        val
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift128;
+
+    /// Counts the `next_u32` words drawn from the wrapped generator.
+    struct Counting {
+        inner: XorShift128,
+        draws: usize,
+    }
+
+    impl UniformRng for Counting {
+        fn from_seed(seed: u32) -> Self {
+            Self {
+                inner: XorShift128::from_seed(seed),
+                draws: 0,
+            }
+        }
+
+        fn next_u32(&mut self) -> u32 {
+            self.draws += 1;
+            self.inner.next_u32()
+        }
+    }
+
+    #[test]
+    fn polar_returns_spare_without_drawing() {
+        let mut rng = Counting::from_seed(1);
+        let mut norm = NormalPolar::new();
+        for _ in 0..1000 {
+            let before = rng.draws;
+            norm.next_standard(&mut rng);
+            let drawn = rng.draws - before;
+            assert!(drawn > 0 && drawn % 4 == 0, "first of pair drew {drawn}");
+
+            let before = rng.draws;
+            norm.next_standard(&mut rng);
+            assert_eq!(rng.draws, before, "spare drew from the generator");
+        }
+    }
+
+    #[test]
+    fn polar_pair_is_u_then_v() {
+        let mut rng = XorShift128::from_seed(7);
+        let mut reference = rng;
+        let mut norm = NormalPolar::new();
+        for _ in 0..1000 {
+            let (u, v, s) = loop {
+                let u = 2.0 * reference.next_f64() - 1.0;
+                let v = 2.0 * reference.next_f64() - 1.0;
+                let s = u * u + v * v;
+                if s > 0.0 && s < 1.0 {
+                    break (u, v, s);
+                }
+            };
+            let m = (-2.0 * s.ln() / s).sqrt();
+            assert_eq!(norm.next_standard(&mut rng).to_bits(), (u * m).to_bits());
+            assert_eq!(norm.next_standard(&mut rng).to_bits(), (v * m).to_bits());
+        }
+    }
+
+    #[test]
+    fn polar_spare_is_per_sampler() {
+        let mut rng = Counting::from_seed(3);
+        let mut a = NormalPolar::new();
+        a.next_standard(&mut rng);
+
+        // `a` holds a spare, but a fresh sampler must still draw.
+        let before = rng.draws;
+        NormalPolar::default().next_standard(&mut rng);
+        assert!(rng.draws > before);
+    }
+
+    /// Repeats two words, so `next_f64` returns one chosen grid point.
+    struct Words([u32; 2], usize);
+
+    impl UniformRng for Words {
+        fn from_seed(seed: u32) -> Self {
+            Self([seed; 2], 0)
+        }
+
+        fn next_u32(&mut self) -> u32 {
+            self.1 += 1;
+            self.0[(self.1 - 1) % 2]
+        }
+    }
+
+    #[test]
+    fn inv_norm_cdf_matches_known_quantiles() {
+        assert_eq!(inv_norm_cdf(0.5), 0.0);
+        assert!((inv_norm_cdf(0.975) - 1.959963984540054).abs() < 1e-15);
+        assert!((inv_norm_cdf(0.025) + 1.959963984540054).abs() < 1e-15);
+    }
+
+    #[test]
+    fn invcdf_end_points_are_finite_and_symmetric() {
+        // Phi^-1(2^-54).
+        const END: f64 = 8.292361075813595;
+        let lo = InvCdf::new().next_standard(&mut Words([0; 2], 0));
+        let hi = InvCdf::new().next_standard(&mut Words([u32::MAX; 2], 0));
+        assert!((lo + END).abs() < 1e-12, "lo={lo}");
+        assert_eq!(hi, -lo);
+    }
+
+    #[test]
+    fn invcdf_grid_is_symmetric_about_one_half() {
+        let mut rng = XorShift128::from_seed(5);
+        for _ in 0..10_000 {
+            let (a, b) = (rng.next_u32(), rng.next_u32());
+            // Complementing both words mirrors the grid index k to 2^53 - 1 - k.
+            let z = InvCdf::new().next_standard(&mut Words([a, b], 0));
+            let mirror = InvCdf::new().next_standard(&mut Words([!a, !b], 0));
+            assert_eq!(z, -mirror, "a={a:#x} b={b:#x}");
+        }
+    }
+}
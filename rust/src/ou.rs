@@ -0,0 +1,309 @@
+//! Ornstein-Uhlenbeck process parameters and the three benchmark phases.
+
+use crate::normal::NormalSampler;
+use crate::rng::UniformRng;
+
+/// Parameters of `dX = theta (mu - X) dt + sigma dW` over `[0, t]`, started at `x0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OuParams {
+    pub t: f64,
+    pub theta: f64,
+    pub mu: f64,
+    pub sigma: f64,
+    pub x0: f64,
+}
+
+impl Default for OuParams {
+    fn default() -> Self {
+        Self {
+            t: 1.0,
+            theta: 1.0,
+            mu: 0.0,
+            sigma: 0.1,
+            x0: 0.0,
+        }
+    }
+}
+
+/// Per-step coefficients of the recurrence `x[i] = a * x[i-1] + b + diff * z[i-1]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coefficients {
+    pub a: f64,
+    pub b: f64,
+    pub diff: f64,
+}
+
+/// Discretization selected with `--scheme`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    /// Euler-Maruyama; first-order accurate in `dt`.
+    Euler,
+    /// Exact Gaussian transition; correct moments on any grid.
+    Exact,
+}
+
+impl Scheme {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scheme::Euler => "euler",
+            Scheme::Exact => "exact",
+        }
+    }
+}
+
+impl OuParams {
+    /// Time of the last point of an `n`-point grid. The grid starts at 0
+    /// with step `t / n`, so it stops one step short of `t`.
+    pub fn terminal_time(&self, n: usize) -> f64 {
+        self.t * (n - 1) as f64 / n as f64
+    }
+
+    /// `E[X(s)]` of the continuous process.
+    pub fn mean_at(&self, s: f64) -> f64 {
+        self.mu + (self.x0 - self.mu) * (-self.theta * s).exp()
+    }
+
+    /// `Var[X(s)]` of the continuous process.
+    pub fn variance_at(&self, s: f64) -> f64 {
+        if self.theta == 0.0 {
+            return self.sigma * self.sigma * s;
+        }
+        self.sigma * self.sigma * -(-2.0 * self.theta * s).exp_m1() / (2.0 * self.theta)
+    }
+
+    /// `E[X]` at the last point of an `n`-point grid.
+    pub fn terminal_mean(&self, n: usize) -> f64 {
+        self.mean_at(self.terminal_time(n))
+    }
+
+    /// `Var[X]` at the last point of an `n`-point grid.
+    pub fn terminal_variance(&self, n: usize) -> f64 {
+        self.variance_at(self.terminal_time(n))
+    }
+
+    /// Coefficients of `scheme` for a grid of `n` points.
+    pub fn coefficients(&self, scheme: Scheme, n: usize) -> Coefficients {
+        match scheme {
+            Scheme::Euler => self.euler(n),
+            Scheme::Exact => self.exact(n),
+        }
+    }
+
+    /// Euler-Maruyama coefficients for a grid of `n` points.
+    pub fn euler(&self, n: usize) -> Coefficients {
+        let dt = self.t / (n as f64);
+        Coefficients {
+            a: 1.0 - self.theta * dt,
+            b: self.theta * self.mu * dt,
+            diff: self.sigma * dt.sqrt(),
+        }
+    }
+
+    /// Exact transition coefficients for a grid of `n` points:
+    /// `a = exp(-theta dt)`, `b = mu (1 - a)` and
+    /// `diff^2 = sigma^2 (1 - exp(-2 theta dt)) / (2 theta)`.
+    pub fn exact(&self, n: usize) -> Coefficients {
+        let dt = self.t / (n as f64);
+        if self.theta == 0.0 {
+            // Brownian limit; the variance formula is 0/0 here.
+            return Coefficients {
+                a: 1.0,
+                b: 0.0,
+                diff: self.sigma * dt.sqrt(),
+            };
+        }
+        let a = (-self.theta * dt).exp();
+        // expm1 keeps 1 - exp(-x) accurate for small theta * dt.
+        let var = self.sigma * self.sigma * -(-2.0 * self.theta * dt).exp_m1() / (2.0 * self.theta);
+        Coefficients {
+            a,
+            b: self.mu * -(-self.theta * dt).exp_m1(),
+            diff: var.sqrt(),
+        }
+    }
+}
+
+/// Fills `gn` with scaled increments `diff * z`.
+#[inline(always)]
+pub fn gen_normals<S: NormalSampler, R: UniformRng>(
+    gn: &mut [f64],
+    diff: f64,
+    norm: &mut S,
+    rng: &mut R,
+) {
+    for g in gn.iter_mut() {
+        *g = diff * norm.next_standard(rng);
+    }
+}
+
+/// Runs the OU recurrence from `x0`; `ou` must be one longer than `gn`.
+#[inline(always)]
+pub fn simulate(ou: &mut [f64], gn: &[f64], a: f64, b: f64, x0: f64) {
+    let n = ou.len();
+    let mut x = x0;
+    ou[0] = x;
+    for i in 1..n {
+        x = a * x + b + gn[i - 1];
+        ou[i] = x;
+    }
+}
+
+/// Sequential sum used as the anti-dead-store readback.
+#[inline(always)]
+pub fn checksum(xs: &[f64]) -> f64 {
+    let mut s = 0.0_f64;
+    for v in xs {
+        s += *v;
+    }
+    s
+}
+
+/// [`gen_normals`] over path-major buffers: path `p` owns
+/// `gn[p * steps..(p + 1) * steps]` and draws from `rngs[p]` and `norms[p]`.
+#[inline(always)]
+pub fn gen_normals_paths<S: NormalSampler, R: UniformRng>(
+    gn: &mut [f64],
+    steps: usize,
+    diff: f64,
+    norms: &mut [S],
+    rngs: &mut [R],
+) {
+    for ((g, norm), rng) in gn.chunks_exact_mut(steps).zip(norms).zip(rngs) {
+        gen_normals(g, diff, norm, rng);
+    }
+}
+
+/// [`simulate`] over path-major buffers of `n` points per path.
+#[inline(always)]
+pub fn simulate_paths(ou: &mut [f64], gn: &[f64], n: usize, a: f64, b: f64, x0: f64) {
+    for (o, g) in ou.chunks_exact_mut(n).zip(gn.chunks_exact(n - 1)) {
+        simulate(o, g, a, b, x0);
+    }
+}
+
+/// Sample statistics of the last point of each path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerminalStats {
+    pub mean: f64,
+    /// Unbiased sample variance; NaN for a single path.
+    pub variance: f64,
+}
+
+/// Terminal statistics of path-major `ou` with `n` points per path.
+pub fn terminal_stats(ou: &[f64], n: usize) -> TerminalStats {
+    let paths = ou.len() / n;
+    let mut mean = 0.0_f64;
+    let mut m2 = 0.0_f64;
+    // Welford's update keeps the variance stable for large path counts.
+    for (k, path) in ou.chunks_exact(n).enumerate() {
+        let x = path[n - 1];
+        let d = x - mean;
+        mean += d / (k + 1) as f64;
+        m2 += d * (x - mean);
+    }
+    let variance = if paths > 1 {
+        m2 / (paths - 1) as f64
+    } else {
+        f64::NAN
+    };
+    TerminalStats { mean, variance }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::streams;
+    use crate::normal::NormalPolar;
+    use crate::rng::XorShift128;
+
+    /// Terminal sample moments over `paths` paths on the benchmark's grid
+    /// of `n` points.
+    fn terminal(p: &OuParams, scheme: Scheme, n: usize, paths: usize) -> TerminalStats {
+        let c = p.coefficients(scheme, n);
+        let (mut rngs, mut norms) = streams::<XorShift128, NormalPolar>(2024, paths);
+        let mut gn = vec![0.0; paths * (n - 1)];
+        let mut ou = vec![0.0; paths * n];
+        gen_normals_paths(&mut gn, n - 1, c.diff, &mut norms, &mut rngs);
+        simulate_paths(&mut ou, &gn, n, c.a, c.b, p.x0);
+        terminal_stats(&ou, n)
+    }
+
+    /// Sample mean and variance within five standard errors of the theory.
+    fn assert_moments(p: &OuParams, n: usize, s: TerminalStats, paths: usize) {
+        let (mean, var) = (p.terminal_mean(n), p.terminal_variance(n));
+        let mean_se = (var / paths as f64).sqrt();
+        let var_se = var * (2.0 / (paths - 1) as f64).sqrt();
+        assert!(
+            (s.mean - mean).abs() < 5.0 * mean_se,
+            "n={} mean {} vs {} (se {})",
+            n,
+            s.mean,
+            mean,
+            mean_se
+        );
+        assert!(
+            (s.variance - var).abs() < 5.0 * var_se,
+            "n={} variance {} vs {} (se {})",
+            n,
+            s.variance,
+            var,
+            var_se
+        );
+    }
+
+    const PATHS: usize = 20_000;
+
+    fn params() -> OuParams {
+        OuParams {
+            t: 1.5,
+            theta: 2.0,
+            mu: 0.5,
+            sigma: 0.3,
+            x0: -1.0,
+        }
+    }
+
+    #[test]
+    fn terminal_moments_are_at_the_last_grid_point() {
+        let p = params();
+        assert_eq!(p.terminal_time(2), p.t / 2.0);
+        assert_eq!(p.terminal_variance(2), p.variance_at(p.t / 2.0));
+        assert!(p.terminal_variance(2) < p.variance_at(p.t));
+    }
+
+    #[test]
+    fn exact_scheme_matches_terminal_moments_on_a_coarse_grid() {
+        let p = params();
+        for n in [2, 5, 50] {
+            assert_moments(&p, n, terminal(&p, Scheme::Exact, n, PATHS), PATHS);
+        }
+    }
+
+    #[test]
+    fn euler_scheme_matches_terminal_moments_on_a_fine_grid() {
+        let p = params();
+        assert_moments(&p, 1000, terminal(&p, Scheme::Euler, 1000, PATHS), PATHS);
+    }
+
+    #[test]
+    fn zero_theta_is_brownian_motion() {
+        let p = OuParams {
+            theta: 0.0,
+            ..params()
+        };
+        assert_eq!(p.mean_at(p.t), p.x0);
+        assert_eq!(p.variance_at(p.t), p.sigma * p.sigma * p.t);
+        for scheme in [Scheme::Euler, Scheme::Exact] {
+            assert_moments(&p, 20, terminal(&p, scheme, 20, PATHS), PATHS);
+        }
+    }
+
+    #[test]
+    fn exact_coefficients_tend_to_euler_for_small_steps() {
+        let p = params();
+        let (e, x) = (p.euler(1_000_000), p.exact(1_000_000));
+        assert!((e.a - x.a).abs() < 1e-11);
+        assert!((e.b - x.b).abs() < 1e-11);
+        assert!((e.diff - x.diff).abs() / x.diff < 1e-5);
+    }
+}
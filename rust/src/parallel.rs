@@ -0,0 +1,415 @@
+//! Multi-threaded runs on scoped std threads.
+//!
+//! With several paths, each thread owns a contiguous range of paths and the
+//! per-path streams are the same as in a serial run, so the simulated paths
+//! do not depend on the thread count; the checksum does, but only through
+//! the order of the partial sums. With a single path, each thread owns a contiguous
+//! chunk of it and draws from substream `thread index` of the seed; the OU
+//! recurrence is then solved as a two-pass parallel scan, so results are
+//! reproducible for a fixed thread count but differ between counts.
+//!
+//! Threads meet at a barrier around every phase and thread 0 takes the
+//! timestamps, so with a wall clock each phase time is that of the slowest
+//! thread. `process-cputime` instead gives the CPU time of all threads;
+//! `thread-cputime` would miss the time thread 0 spends waiting at the
+//! barriers and is rejected by the CLI.
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Barrier;
+use std::thread;
+
+use crate::bench::{self, Config, Mode, Report, Timings};
+use crate::clock::ClockStats;
+use crate::normal::NormalSampler;
+use crate::ou::{self, Coefficients};
+use crate::perf::{Phase, Probe};
+use crate::rng::UniformRng;
+use crate::sched::CpuTrace;
+use crate::stop::{StopRule, WarmupRule};
+
+/// What each thread owns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Split {
+    /// Whole paths.
+    Paths,
+    /// Chunks of the single path's steps.
+    Steps,
+}
+
+struct Part<'a> {
+    gn: &'a mut [f64],
+    ou: &'a mut [f64],
+    /// Substream indices of the generators this thread owns.
+    streams: Range<usize>,
+}
+
+/// Shared by every worker of one run.
+struct Shared<'a> {
+    cfg: &'a Config,
+    split: Split,
+    coef: Coefficients,
+    /// Chunk lengths in steps, per thread; only used by [`Split::Steps`].
+    chunk_lens: Vec<usize>,
+    barrier: Barrier,
+    /// f64 bits of each thread's last local OU value.
+    ends: Vec<AtomicU64>,
+    /// f64 bits of each thread's partial checksum.
+    sums: Vec<AtomicU64>,
+    /// Number of workers.
+    threads: usize,
+    /// Set by thread 0 when warmup should end.
+    warm_done: AtomicBool,
+    /// Set by thread 0 when the timed loop should end.
+    stop: AtomicBool,
+}
+
+pub(crate) fn run_threaded<R, S>(cfg: &Config) -> Report
+where
+    R: UniformRng + Send,
+    S: NormalSampler + Send,
+{
+    let n = cfg.n;
+    let paths = cfg.paths;
+    let steps = n - 1;
+
+    let (split, units) = if paths > 1 {
+        (Split::Paths, paths)
+    } else {
+        (Split::Steps, steps)
+    };
+    let threads = cfg.effective_threads();
+    if threads <= 1 {
+        return bench::run_with::<R, S>(&Config { threads: 1, ..*cfg });
+    }
+
+    let bounds: Vec<usize> = (0..=threads).map(|k| k * units / threads).collect();
+
+    let mut gn = vec![0.0_f64; paths * steps];
+    let mut ou = vec![0.0_f64; paths * n];
+
+    let shared = Shared {
+        cfg,
+        split,
+        coef: cfg.params.coefficients(cfg.scheme, n),
+        chunk_lens: bounds.windows(2).map(|w| w[1] - w[0]).collect(),
+        barrier: Barrier::new(threads),
+        ends: (0..threads).map(|_| AtomicU64::new(0)).collect(),
+        sums: (0..threads).map(|_| AtomicU64::new(0)).collect(),
+        threads,
+        warm_done: AtomicBool::new(false),
+        stop: AtomicBool::new(false),
+    };
+
+    let mut parts = Vec::with_capacity(threads);
+    {
+        let (mut gn_rest, mut ou_rest) = (&mut gn[..], &mut ou[..]);
+        for k in 0..threads {
+            let (lo, hi) = (bounds[k], bounds[k + 1]);
+            let (gn_len, ou_len, streams) = match split {
+                Split::Paths => ((hi - lo) * steps, (hi - lo) * n, lo..hi),
+                // Thread 0 also owns ou[0], the starting value.
+                Split::Steps => (hi - lo, hi - lo + usize::from(k == 0), k..k + 1),
+            };
+            let (g, gr) = gn_rest.split_at_mut(gn_len);
+            let (o, or) = ou_rest.split_at_mut(ou_len);
+            gn_rest = gr;
+            ou_rest = or;
+            parts.push(Part {
+                gn: g,
+                ou: o,
+                streams,
+            });
+        }
+    }
+
+    let mut result = None;
+    thread::scope(|scope| {
+        let mut parts = parts.into_iter().enumerate();
+        let (_, part0) = parts.next().unwrap();
+        for (id, part) in parts {
+            let shared = &shared;
+            scope.spawn(move || {
+                worker::<R, S>(shared, id, part);
+            });
+        }
+        result = worker::<R, S>(&shared, 0, part0);
+    });
+
+    let mut report: Report = result.unwrap();
+    report.terminal = match cfg.mode {
+        Mode::Gn => None,
+        Mode::Full | Mode::Ou => Some(ou::terminal_stats(&ou, n)),
+    };
+    report
+}
+
+/// Runs prefill, warmup and timed runs on one thread's part; thread 0
+/// returns the report, without terminal statistics.
+fn worker<R, S>(sh: &Shared, id: usize, part: Part) -> Option<Report>
+where
+    R: UniformRng,
+    S: NormalSampler,
+{
+    let cfg = sh.cfg;
+    let Part { gn, ou, streams } = part;
+    let new_streams = || -> (Vec<R>, Vec<S>) {
+        let rngs = streams.clone().map(|p| R::stream(cfg.seed, p)).collect();
+        let norms = streams.clone().map(|_| S::default()).collect();
+        (rngs, norms)
+    };
+    let gen_len = match sh.split {
+        Split::Paths => cfg.n - 1,
+        Split::Steps => gn.len(),
+    };
+    let diff = sh.coef.diff;
+    let clock = bench::new_clock(cfg.clock);
+
+    if let Mode::Ou = cfg.mode {
+        let (mut rng, mut norm) = new_streams();
+        ou::gen_normals_paths(gn, gen_len, diff, &mut norm, &mut rng);
+    }
+
+    // Warmup
+    let mut warm = WarmupRule::new(cfg.warmup);
+    let mut warm_times = Vec::new();
+    {
+        let (mut rng, mut norm) = new_streams();
+        loop {
+            if id == 0 {
+                sh.warm_done
+                    .store(warm.done(&warm_times), Ordering::Relaxed);
+            }
+            sh.barrier.wait();
+            if sh.warm_done.load(Ordering::Relaxed) {
+                break;
+            }
+            let t0 = clock.now();
+            if cfg.mode != Mode::Ou {
+                ou::gen_normals_paths(gn, gen_len, diff, &mut norm, &mut rng);
+            }
+            sh.barrier.wait();
+            if cfg.mode != Mode::Gn {
+                simulate(sh, id, gn, ou);
+            }
+            let s = partial_checksum(sh, id, gn, ou);
+            if s == 123456789.0 {
+                eprintln!("impossible");
+            }
+            if id == 0 {
+                warm_times.push(clock.secs(t0, clock.now()));
+            }
+        }
+    }
+
+    // Timed runs
+    let clock_stats = if id == 0 {
+        clock.stats()
+    } else {
+        ClockStats::default()
+    };
+    let (mut rng, mut norm) = new_streams();
+    let mut timings = Timings::new(cfg.runs, bench::timer_overhead(cfg, &clock_stats));
+    let mut checksum = 0.0_f64;
+    let mut stop = StopRule::new(cfg.runs, cfg.adaptive);
+    // Counts cover the timing thread only.
+    let mut probe = Probe::new(cfg.perf_counters && id == 0);
+    let mut cpus = CpuTrace::default();
+
+    loop {
+        // Thread 0 owns the stop decision and publishes it before the
+        // barrier that starts each run.
+        if id == 0 {
+            sh.stop
+                .store(stop.done(timings.run_times()), Ordering::Relaxed);
+        }
+        sh.barrier.wait();
+        if sh.stop.load(Ordering::Relaxed) {
+            break;
+        }
+        probe.start();
+        let t0 = clock.now();
+        if cfg.mode != Mode::Ou {
+            ou::gen_normals_paths(gn, gen_len, diff, &mut norm, &mut rng);
+        }
+        sh.barrier.wait();
+        let t1 = clock.now();
+        probe.end(Phase::Gen);
+        if cfg.mode != Mode::Gn {
+            simulate(sh, id, gn, ou);
+        }
+        sh.barrier.wait();
+        let t2 = clock.now();
+        probe.end(Phase::Sim);
+        let s = partial_checksum(sh, id, gn, ou);
+        let t3 = clock.now();
+        probe.end(Phase::Chk);
+
+        if id == 0 {
+            checksum += s;
+            let gen = clock.secs(t0, t1);
+            let sim = clock.secs(t1, t2);
+            let chk = clock.secs(t2, t3);
+            let run = clock.secs(t0, t3);
+            match cfg.mode {
+                Mode::Full => timings.record(gen, sim, chk, run),
+                Mode::Gn => timings.record(gen, 0.0, chk, gen + chk),
+                Mode::Ou => timings.record(0.0, sim, chk, sim + chk),
+            }
+            cpus.sample();
+        }
+    }
+
+    (id == 0).then(|| {
+        let mut report = timings.into_report(checksum, None, sh.threads, stop.reason);
+        report.perf = probe.finish();
+        report.clock = clock_stats;
+        report.overhead_subtracted = cfg.subtract_timer_overhead;
+        report.cpus = cpus;
+        report.warmup_runs = warm_times.len();
+        report.warmup_converged = warm.converged;
+        report
+    })
+}
+
+/// OU phase for one thread's part.
+fn simulate(sh: &Shared, id: usize, gn: &[f64], ou: &mut [f64]) {
+    let Coefficients { a, b, .. } = sh.coef;
+    let x0 = sh.cfg.params.x0;
+    match sh.split {
+        Split::Paths => ou::simulate_paths(ou, gn, sh.cfg.n, a, b, x0),
+        Split::Steps => {
+            // Pass 1: thread 0 runs the true recurrence from x0; the others
+            // run it from 0 and publish their last value.
+            if id == 0 {
+                ou::simulate(ou, gn, a, b, x0);
+            } else {
+                let mut x = 0.0_f64;
+                for (o, g) in ou.iter_mut().zip(gn) {
+                    x = a * x + b + g;
+                    *o = x;
+                }
+            }
+            sh.ends[id].store(ou[ou.len() - 1].to_bits(), Ordering::Relaxed);
+            sh.barrier.wait();
+
+            // Pass 2: add the carried-in value c decayed by a^(j+1).
+            if id > 0 {
+                let mut c = f64::from_bits(sh.ends[0].load(Ordering::Relaxed));
+                for k in 1..id {
+                    let end = f64::from_bits(sh.ends[k].load(Ordering::Relaxed));
+                    c = a.powf(sh.chunk_lens[k] as f64) * c + end;
+                }
+                for o in ou.iter_mut() {
+                    c *= a;
+                    *o += c;
+                }
+            }
+        }
+    }
+}
+
+/// Checksum phase; every thread sums its part and thread 0 adds the
+/// partial sums in thread order. Returns the total on thread 0.
+fn partial_checksum(sh: &Shared, id: usize, gn: &[f64], ou: &[f64]) -> f64 {
+    let s = match sh.cfg.mode {
+        Mode::Gn => ou::checksum(gn),
+        Mode::Full | Mode::Ou => ou::checksum(ou),
+    };
+    sh.sums[id].store(s.to_bits(), Ordering::Relaxed);
+    sh.barrier.wait();
+    if id != 0 {
+        return 0.0;
+    }
+    sh.sums
+        .iter()
+        .map(|v| f64::from_bits(v.load(Ordering::Relaxed)))
+        .fold(0.0, |acc, v| acc + v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normal::NormalPolar;
+    use crate::ou::{OuParams, Scheme};
+    use crate::rng::XorShift128;
+    use crate::stop::Warmup;
+
+    fn run(cfg: &Config, threads: usize) -> Report {
+        bench::run_with::<XorShift128, NormalPolar>(&Config { threads, ..*cfg })
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-12 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    #[test]
+    fn step_scan_matches_serial_recurrence() {
+        // Without noise every thread count must reproduce the serial path;
+        // 999 steps over 2, 3 or 7 threads gives unequal chunks, which
+        // exercises the a^len carry-in.
+        for scheme in [Scheme::Euler, Scheme::Exact] {
+            let cfg = Config {
+                n: 1000,
+                runs: 1,
+                warmup: Warmup::Fixed(0),
+                scheme,
+                params: OuParams {
+                    theta: 3.0,
+                    mu: 0.4,
+                    sigma: 0.0,
+                    x0: 2.0,
+                    ..OuParams::default()
+                },
+                ..Config::default()
+            };
+            let serial = run(&cfg, 1);
+            let last = serial.terminal.unwrap().mean;
+            for threads in [2, 3, 7] {
+                let par = run(&cfg, threads);
+                assert_eq!(par.threads, threads);
+                assert!(
+                    close(par.checksum, serial.checksum),
+                    "{} threads={}: checksum {} vs {}",
+                    scheme.as_str(),
+                    threads,
+                    par.checksum,
+                    serial.checksum
+                );
+                let par_last = par.terminal.unwrap().mean;
+                assert!(
+                    close(par_last, last),
+                    "{} threads={}: last point {} vs {}",
+                    scheme.as_str(),
+                    threads,
+                    par_last,
+                    last
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn path_split_does_not_change_the_paths() {
+        let cfg = Config {
+            n: 200,
+            paths: 8,
+            runs: 1,
+            warmup: Warmup::Fixed(0),
+            params: OuParams {
+                x0: 0.5,
+                ..OuParams::default()
+            },
+            ..Config::default()
+        };
+        let serial = run(&cfg, 1);
+        for threads in [2, 3, 8] {
+            let par = run(&cfg, threads);
+            assert_eq!(par.threads, threads);
+            // Same per-path streams, so the terminal values are identical;
+            // only the checksum's summation order differs.
+            assert_eq!(par.terminal, serial.terminal, "threads={}", threads);
+            assert!(close(par.checksum, serial.checksum), "threads={}", threads);
+        }
+    }
+}
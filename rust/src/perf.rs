@@ -0,0 +1,382 @@
+//! Hardware performance counters via Linux `perf_event_open`.
+//!
+//! The five events are opened as one group on the calling thread, user
+//! space only, so they are scheduled together and read with a single
+//! `read` per phase boundary. Events the CPU lacks are left out of the
+//! group and reported as missing; if the group cannot be opened or read
+//! the error is reported instead of counts. When the kernel multiplexes
+//! the group with other events, counts are scaled up by enabled/running
+//! time as `perf stat` does.
+
+use std::fmt;
+
+/// Counted events, in group order.
+pub const EVENTS: [&str; 5] = [
+    "cycles",
+    "instructions",
+    "branch_misses",
+    "cache_misses",
+    "l1d_misses",
+];
+
+/// Summed counts of one phase over the timed runs; `None` for events that
+/// could not be opened, or for every event if the group never ran during
+/// the phase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventCounts(pub [Option<u64>; 5]);
+
+impl EventCounts {
+    pub fn get(&self, event: usize) -> Option<u64> {
+        self.0[event]
+    }
+
+    /// Instructions per cycle.
+    pub fn ipc(&self) -> Option<f64> {
+        match (self.0[0], self.0[1]) {
+            (Some(cyc), Some(ins)) if cyc > 0 => Some(ins as f64 / cyc as f64),
+            _ => None,
+        }
+    }
+}
+
+/// Counts per phase, summed over the timed runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PerfCounts {
+    pub gen: EventCounts,
+    pub sim: EventCounts,
+    pub chk: EventCounts,
+    /// Share of the enabled time the group was on the PMU; below 1 when
+    /// it was multiplexed and the counts are scaled estimates.
+    pub running_fraction: f64,
+}
+
+/// Why counters are unavailable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PerfError(pub String);
+
+impl fmt::Display for PerfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Phase {
+    Gen = 0,
+    Sim = 1,
+    Chk = 2,
+}
+
+/// One group read: cumulative counts (0 for missing events) and the
+/// group's enabled and running times in ns.
+#[derive(Debug, Clone, Copy, Default)]
+struct Reading {
+    counts: [u64; 5],
+    enabled: u64,
+    running: u64,
+}
+
+/// Accumulates group deltas per phase; without a group every call is a
+/// no-op, so the timed loop can call it unconditionally.
+pub(crate) struct Probe {
+    group: Option<sys::Group>,
+    last: Reading,
+    totals: [[u64; 5]; 3],
+    /// Enabled and running time per phase.
+    times: [(u64, u64); 3],
+    error: Option<PerfError>,
+}
+
+impl Probe {
+    /// Opens the group on the calling thread when `enabled`; failures are
+    /// kept for the report.
+    pub(crate) fn new(enabled: bool) -> Self {
+        let mut probe = Self {
+            group: None,
+            last: Reading::default(),
+            totals: [[0; 5]; 3],
+            times: [(0, 0); 3],
+            error: None,
+        };
+        if enabled {
+            match sys::Group::open() {
+                Ok(group) => probe.group = Some(group),
+                Err(e) => probe.error = Some(e),
+            }
+        }
+        probe
+    }
+
+    /// Reads the group; a failed read drops it and keeps the error, so
+    /// later calls are no-ops.
+    #[inline(always)]
+    fn read(&mut self) -> Option<Reading> {
+        match self.group.as_ref()?.read() {
+            Ok(r) => Some(r),
+            Err(e) => {
+                self.group = None;
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+
+    /// Marks the start of a run.
+    #[inline(always)]
+    pub(crate) fn start(&mut self) {
+        if let Some(now) = self.read() {
+            self.last = now;
+        }
+    }
+
+    /// Charges the counts since the previous mark to `phase`.
+    #[inline(always)]
+    pub(crate) fn end(&mut self, phase: Phase) {
+        if let Some(now) = self.read() {
+            let total = &mut self.totals[phase as usize];
+            for ((t, n), l) in total.iter_mut().zip(now.counts).zip(self.last.counts) {
+                *t += n - l;
+            }
+            let times = &mut self.times[phase as usize];
+            times.0 += now.enabled - self.last.enabled;
+            times.1 += now.running - self.last.running;
+            self.last = now;
+        }
+    }
+
+    /// `None` if counters were not requested.
+    pub(crate) fn finish(self) -> Option<Result<PerfCounts, PerfError>> {
+        if let Some(e) = self.error {
+            return Some(Err(e));
+        }
+        let group = self.group?;
+        let enabled: u64 = self.times.iter().map(|t| t.0).sum();
+        let running: u64 = self.times.iter().map(|t| t.1).sum();
+        if running == 0 {
+            return Some(Err(PerfError(
+                "perf counter group was never scheduled on the PMU".to_string(),
+            )));
+        }
+        let phase = |p: usize| {
+            let (enabled, running) = self.times[p];
+            let mut c = EventCounts::default();
+            if running == 0 {
+                return c;
+            }
+            for (k, slot) in c.0.iter_mut().enumerate() {
+                let raw = self.totals[p][k] as u128;
+                let scaled = raw * enabled.max(running) as u128 / running as u128;
+                *slot = group.has(k).then_some(scaled as u64);
+            }
+            c
+        };
+        Some(Ok(PerfCounts {
+            gen: phase(Phase::Gen as usize),
+            sim: phase(Phase::Sim as usize),
+            chk: phase(Phase::Chk as usize),
+            running_fraction: running as f64 / enabled.max(running) as f64,
+        }))
+    }
+}
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod sys {
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::os::raw::{c_int, c_long, c_ulong};
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+
+    use super::{PerfError, Reading};
+
+    #[cfg(target_arch = "x86_64")]
+    const SYS_PERF_EVENT_OPEN: c_long = 298;
+    #[cfg(target_arch = "aarch64")]
+    const SYS_PERF_EVENT_OPEN: c_long = 241;
+
+    const PERF_TYPE_HARDWARE: u32 = 0;
+    const PERF_TYPE_HW_CACHE: u32 = 3;
+    const PERF_COUNT_HW_CPU_CYCLES: u64 = 0;
+    const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+    const PERF_COUNT_HW_CACHE_MISSES: u64 = 3;
+    const PERF_COUNT_HW_BRANCH_MISSES: u64 = 5;
+    /// L1D | (OP_READ << 8) | (RESULT_MISS << 16).
+    const PERF_COUNT_HW_CACHE_L1D_READ_MISS: u64 = 1 << 16;
+
+    const PERF_FORMAT_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+    const PERF_FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+    const PERF_FORMAT_GROUP: u64 = 1 << 3;
+
+    const FLAG_DISABLED: u64 = 1 << 0;
+    const FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+    const FLAG_EXCLUDE_HV: u64 = 1 << 6;
+
+    const PERF_EVENT_IOC_ENABLE: c_ulong = 0x2400;
+    const PERF_EVENT_IOC_RESET: c_ulong = 0x2403;
+    const PERF_IOC_FLAG_GROUP: c_ulong = 1;
+
+    const EVENTS: [(u32, u64); 5] = [
+        (PERF_TYPE_HARDWARE, PERF_COUNT_HW_CPU_CYCLES),
+        (PERF_TYPE_HARDWARE, PERF_COUNT_HW_INSTRUCTIONS),
+        (PERF_TYPE_HARDWARE, PERF_COUNT_HW_BRANCH_MISSES),
+        (PERF_TYPE_HARDWARE, PERF_COUNT_HW_CACHE_MISSES),
+        (PERF_TYPE_HW_CACHE, PERF_COUNT_HW_CACHE_L1D_READ_MISS),
+    ];
+
+    extern "C" {
+        fn syscall(num: c_long, ...) -> c_long;
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+    }
+
+    /// `struct perf_event_attr`, `PERF_ATTR_SIZE_VER5` layout.
+    #[repr(C)]
+    #[derive(Default)]
+    struct Attr {
+        type_: u32,
+        size: u32,
+        config: u64,
+        sample_period: u64,
+        sample_type: u64,
+        read_format: u64,
+        flags: u64,
+        wakeup_events: u32,
+        bp_type: u32,
+        config1: u64,
+        config2: u64,
+        branch_sample_type: u64,
+        sample_regs_user: u64,
+        sample_stack_user: u32,
+        clockid: i32,
+        sample_regs_intr: u64,
+        aux_watermark: u32,
+        sample_max_stack: u16,
+        reserved: u16,
+    }
+
+    pub(super) struct Group {
+        /// Leader first; members in the order they were opened.
+        files: Vec<File>,
+        /// Group slot of each event, `None` if it could not be opened.
+        slots: [Option<usize>; 5],
+    }
+
+    fn open_event(type_: u32, config: u64, group_fd: c_int) -> io::Result<File> {
+        let mut flags = FLAG_EXCLUDE_KERNEL | FLAG_EXCLUDE_HV;
+        if group_fd == -1 {
+            flags |= FLAG_DISABLED;
+        }
+        let attr = Attr {
+            type_,
+            size: std::mem::size_of::<Attr>() as u32,
+            config,
+            read_format: PERF_FORMAT_GROUP
+                | PERF_FORMAT_TOTAL_TIME_ENABLED
+                | PERF_FORMAT_TOTAL_TIME_RUNNING,
+            flags,
+            ..Attr::default()
+        };
+        // pid 0, cpu -1: the calling thread on any CPU.
+        let fd = unsafe {
+            syscall(
+                SYS_PERF_EVENT_OPEN,
+                &attr as *const Attr,
+                0 as c_int,
+                -1 as c_int,
+                group_fd,
+                0 as c_ulong,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(unsafe { File::from_raw_fd(fd as c_int) })
+    }
+
+    impl Group {
+        pub(super) fn open() -> Result<Self, PerfError> {
+            let (type_, config) = EVENTS[0];
+            let leader = open_event(type_, config, -1)
+                .map_err(|e| PerfError(format!("perf_event_open failed: {}", e)))?;
+            let leader_fd = leader.as_raw_fd();
+            let mut files = vec![leader];
+            let mut slots = [Some(0), None, None, None, None];
+            for (k, &(type_, config)) in EVENTS.iter().enumerate().skip(1) {
+                if let Ok(f) = open_event(type_, config, leader_fd) {
+                    slots[k] = Some(files.len());
+                    files.push(f);
+                }
+            }
+            let ok = unsafe {
+                ioctl(leader_fd, PERF_EVENT_IOC_RESET, PERF_IOC_FLAG_GROUP) == 0
+                    && ioctl(leader_fd, PERF_EVENT_IOC_ENABLE, PERF_IOC_FLAG_GROUP) == 0
+            };
+            if !ok {
+                return Err(PerfError(format!(
+                    "cannot enable perf counters: {}",
+                    io::Error::last_os_error()
+                )));
+            }
+            Ok(Self { files, slots })
+        }
+
+        pub(super) fn has(&self, event: usize) -> bool {
+            self.slots[event].is_some()
+        }
+
+        pub(super) fn read(&self) -> Result<Reading, PerfError> {
+            // nr, time_enabled, time_running, then one value per member.
+            let mut buf = [0u8; 8 * (3 + 5)];
+            let len = 8 * (3 + self.files.len());
+            let mut leader = &self.files[0];
+            let got = leader
+                .read(&mut buf[..len])
+                .map_err(|e| PerfError(format!("cannot read perf counters: {}", e)))?;
+            if got != len {
+                return Err(PerfError(format!(
+                    "short read of perf counters: {} of {} bytes",
+                    got, len
+                )));
+            }
+            let word = |i: usize| u64::from_ne_bytes(buf[8 * i..8 * i + 8].try_into().unwrap());
+            let mut counts = [0u64; 5];
+            for (k, slot) in self.slots.iter().enumerate() {
+                if let Some(s) = slot {
+                    counts[k] = word(3 + s);
+                }
+            }
+            Ok(Reading {
+                counts,
+                enabled: word(1),
+                running: word(2),
+            })
+        }
+    }
+}
+
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+mod sys {
+    use super::{PerfError, Reading};
+
+    pub(super) enum Group {}
+
+    impl Group {
+        pub(super) fn open() -> Result<Self, PerfError> {
+            Err(PerfError(
+                "perf counters need Linux on x86_64 or aarch64".to_string(),
+            ))
+        }
+
+        pub(super) fn has(&self, _event: usize) -> bool {
+            match *self {}
+        }
+
+        pub(super) fn read(&self) -> Result<Reading, PerfError> {
+            match *self {}
+        }
+    }
+}
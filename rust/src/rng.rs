@@ -0,0 +1,574 @@
+//! Uniform pseudo-random generators shared by every language port.
+//!
+//! [`XorShift128`] is the generator every language implementation uses; the
+//! others exist so the `gen_normals` phase can be split into generator cost
+//! versus sampler cost.
+
+/// A seedable source of uniform bits.
+///
+/// 32-bit generators implement [`next_u32`](UniformRng::next_u32) and inherit
+/// the rest; 64-bit generators should also override `next_u64` and `next_f64`
+/// so no output bits are thrown away.
+pub trait UniformRng {
+    /// Builds a generator whose state is derived from a 32-bit seed.
+    fn from_seed(seed: u32) -> Self
+    where
+        Self: Sized;
+
+    /// Builds the generator for substream `index` of `seed`, used to give
+    /// each path its own stream. Substream 0 is `from_seed(seed)`.
+    fn stream(seed: u32, index: usize) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_seed(stream_seed(seed, index))
+    }
+
+    fn next_u32(&mut self) -> u32;
+
+    #[inline(always)]
+    fn next_u64(&mut self) -> u64 {
+        let hi = self.next_u32() as u64;
+        let lo = self.next_u32() as u64;
+        (hi << 32) | lo
+    }
+
+    /// 53-bit uniform in [0,1).
+    #[inline(always)]
+    fn next_f64(&mut self) -> f64 {
+        // Two u32 draws, 27 + 26 bits, as in the reference implementations.
+        let a = self.next_u32();
+        let b = self.next_u32();
+        let u: u64 = ((a >> 5) as u64) << 26 | ((b >> 6) as u64);
+        (u as f64) * (1.0 / 9007199254740992.0) // 2^53
+    }
+}
+
+/// Generator selected with `--rng`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngKind {
+    XorShift128,
+    SplitMix32,
+    Pcg32,
+    Xoshiro256StarStar,
+    WyRand,
+}
+
+impl RngKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RngKind::XorShift128 => "xorshift128",
+            RngKind::SplitMix32 => "splitmix32",
+            RngKind::Pcg32 => "pcg32",
+            RngKind::Xoshiro256StarStar => "xoshiro256ss",
+            RngKind::WyRand => "wyrand",
+        }
+    }
+}
+
+/// Seed of substream `index`: `seed` itself for index 0, otherwise the
+/// `index`-th draw of a splitmix32 stream started at `seed`.
+pub fn stream_seed(seed: u32, index: usize) -> u32 {
+    if index == 0 {
+        return seed;
+    }
+    // splitmix32 is counter based, so jump straight to the wanted draw.
+    let mut sm = SplitMix32::new(seed);
+    let skip = 0x9E37_79B9_u32.wrapping_mul((index - 1) as u32);
+    sm.s = sm.s.wrapping_add(skip);
+    sm.next_u32()
+}
+
+#[inline(always)]
+fn u64_from_halves(sm: &mut SplitMix32) -> u64 {
+    let hi = sm.next_u32() as u64;
+    let lo = sm.next_u32() as u64;
+    (hi << 32) | lo
+}
+
+#[inline(always)]
+fn u64_to_f64(x: u64) -> f64 {
+    ((x >> 11) as f64) * (1.0 / 9007199254740992.0) // 2^53
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SplitMix32 {
+    pub s: u32,
+}
+
+impl SplitMix32 {
+    pub fn new(seed: u32) -> Self {
+        Self { s: seed }
+    }
+
+    #[inline(always)]
+    pub fn next_u32(&mut self) -> u32 {
+        self.s = self.s.wrapping_add(0x9E37_79B9);
+        let mut z = self.s;
+        z = (z ^ (z >> 16)).wrapping_mul(0x85EB_CA6B);
+        z = (z ^ (z >> 13)).wrapping_mul(0xC2B2_AE35);
+        z ^ (z >> 16)
+    }
+}
+
+impl UniformRng for SplitMix32 {
+    fn from_seed(seed: u32) -> Self {
+        Self::new(seed)
+    }
+
+    #[inline(always)]
+    fn next_u32(&mut self) -> u32 {
+        SplitMix32::next_u32(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct XorShift128 {
+    x: u32,
+    y: u32,
+    z: u32,
+    w: u32,
+}
+
+impl XorShift128 {
+    /// Seeds the four state words from a splitmix32 stream.
+    pub fn new(seed: u32) -> Self {
+        let mut sm = SplitMix32::new(seed);
+        let x = sm.next_u32();
+        let y = sm.next_u32();
+        let z = sm.next_u32();
+        let mut w = sm.next_u32();
+
+        if (x | y | z | w) == 0 {
+            w = 1;
+        }
+
+        Self { x, y, z, w }
+    }
+
+    #[inline(always)]
+    pub fn next_u32(&mut self) -> u32 {
+        // Marsaglia xorshift128 (32-bit)
+        let t = self.x ^ (self.x << 11);
+        self.x = self.y;
+        self.y = self.z;
+        self.z = self.w;
+        self.w = self.w ^ (self.w >> 19) ^ t ^ (t >> 8);
+        self.w
+    }
+
+    #[inline(always)]
+    pub fn next_f64(&mut self) -> f64 {
+        // 53-bit uniform in [0,1) from two u32 draws.
+        let a = self.next_u32();
+        let b = self.next_u32();
+        let u: u64 = ((a >> 5) as u64) << 26 | ((b >> 6) as u64);
+        (u as f64) * (1.0 / 9007199254740992.0) // 2^53
+    }
+}
+
+/// Characteristic polynomial of the xorshift128 transition over GF(2),
+/// without its leading `x^128` term. Bit `i` is the coefficient of `x^i`.
+const XS128_CHARPOLY: u128 = 0x0000_0001_0046_D8B3_F985_D65F_FD3C_8001;
+
+/// `x^(2^64)` modulo [`XS128_CHARPOLY`].
+const XS128_JUMP: u128 = 0xD8CD_644E_F52E_65C4_821E_5343_35AA_C71C;
+
+impl XorShift128 {
+    /// Advances the state by 2^64 draws of `next_u32`.
+    ///
+    /// The period is 2^128 - 1, so streams `jump`ed 0, 1, 2, ... times from
+    /// the same seed never overlap within 2^64 draws.
+    pub fn jump(&mut self) {
+        self.apply_poly(XS128_JUMP);
+    }
+
+    /// Advances the state by `k * 2^64` draws in O(log k).
+    pub fn jump_n(&mut self, k: u64) {
+        self.apply_poly(gf2_pow(XS128_JUMP, k as u128));
+    }
+
+    /// Advances the state by `steps` draws of `next_u32` in O(log steps).
+    pub fn advance(&mut self, steps: u128) {
+        self.apply_poly(gf2_pow(0b10, steps));
+    }
+
+    /// Replaces the state `s` by `q(T) s`, where `T` is one transition.
+    /// With `q = x^k mod charpoly` this equals `T^k s`.
+    fn apply_poly(&mut self, q: u128) {
+        let mut acc = [0_u32; 4];
+        for i in 0..128 {
+            if (q >> i) & 1 == 1 {
+                acc[0] ^= self.x;
+                acc[1] ^= self.y;
+                acc[2] ^= self.z;
+                acc[3] ^= self.w;
+            }
+            self.next_u32();
+        }
+        [self.x, self.y, self.z, self.w] = acc;
+    }
+}
+
+/// `a * b` modulo `x^128 + XS128_CHARPOLY` over GF(2).
+fn gf2_mulmod(a: u128, b: u128) -> u128 {
+    let mut r = 0_u128;
+    for i in (0..128).rev() {
+        let carry = r >> 127;
+        r <<= 1;
+        if carry == 1 {
+            r ^= XS128_CHARPOLY;
+        }
+        if (b >> i) & 1 == 1 {
+            r ^= a;
+        }
+    }
+    r
+}
+
+/// `base^e` modulo `x^128 + XS128_CHARPOLY` over GF(2).
+fn gf2_pow(mut base: u128, mut e: u128) -> u128 {
+    let mut r = 1_u128;
+    while e != 0 {
+        if e & 1 == 1 {
+            r = gf2_mulmod(r, base);
+        }
+        base = gf2_mulmod(base, base);
+        e >>= 1;
+    }
+    r
+}
+
+impl UniformRng for XorShift128 {
+    fn from_seed(seed: u32) -> Self {
+        Self::new(seed)
+    }
+
+    /// Substream `index` is the seeded state jumped `index` times, so
+    /// substreams are provably disjoint.
+    fn stream(seed: u32, index: usize) -> Self {
+        let mut rng = Self::new(seed);
+        rng.jump_n(index as u64);
+        rng
+    }
+
+    #[inline(always)]
+    fn next_u32(&mut self) -> u32 {
+        XorShift128::next_u32(self)
+    }
+
+    #[inline(always)]
+    fn next_f64(&mut self) -> f64 {
+        XorShift128::next_f64(self)
+    }
+}
+
+/// O'Neill's PCG32 (XSH-RR 64/32).
+#[derive(Debug, Clone, Copy)]
+pub struct Pcg32 {
+    state: u64,
+    inc: u64,
+}
+
+impl Pcg32 {
+    const MULT: u64 = 6364136223846793005;
+
+    /// Seeds `initstate` and `initseq` from a splitmix32 stream.
+    pub fn new(seed: u32) -> Self {
+        let mut sm = SplitMix32::new(seed);
+        let initstate = u64_from_halves(&mut sm);
+        let initseq = u64_from_halves(&mut sm);
+        let mut rng = Self {
+            state: 0,
+            inc: (initseq << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(initstate);
+        rng.next_u32();
+        rng
+    }
+
+    #[inline(always)]
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(Self::MULT).wrapping_add(self.inc);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+}
+
+impl UniformRng for Pcg32 {
+    fn from_seed(seed: u32) -> Self {
+        Self::new(seed)
+    }
+
+    #[inline(always)]
+    fn next_u32(&mut self) -> u32 {
+        Pcg32::next_u32(self)
+    }
+}
+
+/// Blackman and Vigna's xoshiro256**.
+#[derive(Debug, Clone, Copy)]
+pub struct Xoshiro256StarStar {
+    s: [u64; 4],
+}
+
+impl Xoshiro256StarStar {
+    /// Seeds the four state words from a splitmix32 stream.
+    pub fn new(seed: u32) -> Self {
+        let mut sm = SplitMix32::new(seed);
+        let mut s = [0_u64; 4];
+        for w in s.iter_mut() {
+            *w = u64_from_halves(&mut sm);
+        }
+        if s == [0; 4] {
+            s[3] = 1;
+        }
+        Self { s }
+    }
+
+    #[inline(always)]
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.s;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+}
+
+impl UniformRng for Xoshiro256StarStar {
+    fn from_seed(seed: u32) -> Self {
+        Self::new(seed)
+    }
+
+    #[inline(always)]
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    #[inline(always)]
+    fn next_u64(&mut self) -> u64 {
+        Xoshiro256StarStar::next_u64(self)
+    }
+
+    #[inline(always)]
+    fn next_f64(&mut self) -> f64 {
+        u64_to_f64(self.next_u64())
+    }
+}
+
+/// Wang Yi's wyrand.
+#[derive(Debug, Clone, Copy)]
+pub struct WyRand {
+    state: u64,
+}
+
+impl WyRand {
+    pub fn new(seed: u32) -> Self {
+        let mut sm = SplitMix32::new(seed);
+        Self {
+            state: u64_from_halves(&mut sm),
+        }
+    }
+
+    #[inline(always)]
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0xA076_1D64_78BD_642F);
+        let t = (self.state as u128).wrapping_mul((self.state ^ 0xE703_7ED1_A0B4_28DB) as u128);
+        ((t >> 64) ^ t) as u64
+    }
+}
+
+impl UniformRng for WyRand {
+    fn from_seed(seed: u32) -> Self {
+        Self::new(seed)
+    }
+
+    #[inline(always)]
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    #[inline(always)]
+    fn next_u64(&mut self) -> u64 {
+        WyRand::next_u64(self)
+    }
+
+    #[inline(always)]
+    fn next_f64(&mut self) -> f64 {
+        u64_to_f64(self.next_u64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(r: &XorShift128) -> [u32; 4] {
+        [r.x, r.y, r.z, r.w]
+    }
+
+    fn stepped(mut r: XorShift128, steps: usize) -> XorShift128 {
+        for _ in 0..steps {
+            r.next_u32();
+        }
+        r
+    }
+
+    #[test]
+    fn advance_matches_brute_force() {
+        let start = XorShift128::new(42);
+        for steps in (0..300).chain([1000, 4095, 4096, 4097, 65_536]) {
+            let mut jumped = start;
+            jumped.advance(steps as u128);
+            assert_eq!(
+                state(&jumped),
+                state(&stepped(start, steps)),
+                "steps={steps}"
+            );
+        }
+    }
+
+    #[test]
+    fn power_of_two_jumps_match_brute_force() {
+        let start = XorShift128::new(7);
+        for k in 0..=16 {
+            let mut jumped = start;
+            jumped.apply_poly(gf2_pow(0b10, 1 << k));
+            assert_eq!(state(&jumped), state(&stepped(start, 1 << k)), "k={k}");
+        }
+    }
+
+    #[test]
+    fn jump_polynomial_is_x_pow_2_64() {
+        assert_eq!(gf2_pow(0b10, 1 << 64), XS128_JUMP);
+    }
+
+    #[test]
+    fn jump_n_composes_jumps() {
+        let mut a = XorShift128::new(1);
+        let mut b = a;
+        for _ in 0..5 {
+            a.jump();
+        }
+        b.jump_n(5);
+        assert_eq!(state(&a), state(&b));
+
+        let mut c = XorShift128::new(1);
+        c.advance(5 << 64);
+        assert_eq!(state(&a), state(&c));
+    }
+
+    #[test]
+    fn stream_zero_is_the_seeded_generator() {
+        assert_eq!(
+            state(&XorShift128::stream(9, 0)),
+            state(&XorShift128::new(9))
+        );
+    }
+
+    #[test]
+    fn splitmix32_matches_reference_vectors() {
+        for (seed, expected) in [
+            (
+                0,
+                [
+                    0x92ca2f0e, 0x3cd6e3f3, 0x1b147dcc, 0x4c081dbf, 0x487981ab, 0xdb408c9d,
+                ],
+            ),
+            (
+                1,
+                [
+                    0x96a0f96b, 0x12bc8390, 0x971e9964, 0x79adc7e7, 0x591c8dd8, 0xcd6587c9,
+                ],
+            ),
+        ] {
+            let mut r = SplitMix32::new(seed);
+            let got: Vec<u32> = (0..expected.len()).map(|_| r.next_u32()).collect();
+            assert_eq!(got, expected, "seed={seed}");
+        }
+    }
+
+    #[test]
+    fn xorshift128_matches_reference_vectors() {
+        for (seed, expected) in [
+            (
+                1,
+                [
+                    0xe8570218, 0x1e01bc81, 0x7db7d39c, 0x6a32b132, 0x3a2539ae, 0x29d36fdf,
+                ],
+            ),
+            (
+                12345,
+                [
+                    0x457223c5, 0x63c8d4dd, 0xa69ade3c, 0x0267648c, 0xd6df030e, 0xf3944b34,
+                ],
+            ),
+        ] {
+            let mut r = XorShift128::new(seed);
+            let got: Vec<u32> = (0..expected.len()).map(|_| r.next_u32()).collect();
+            assert_eq!(got, expected, "seed={seed}");
+        }
+    }
+
+    #[test]
+    fn xorshift128_next_f64_matches_reference() {
+        let mut r = XorShift128::new(1);
+        assert_eq!(r.next_f64(), 8174732595169010.0 / 9007199254740992.0);
+    }
+
+    /// Returns the same word forever, to probe the ends of `next_f64`.
+    struct Constant(u32);
+
+    impl UniformRng for Constant {
+        fn from_seed(seed: u32) -> Self {
+            Self(seed)
+        }
+
+        fn next_u32(&mut self) -> u32 {
+            self.0
+        }
+    }
+
+    /// Checks that `next_f64` stays in [0,1) on a 2^-53 grid and that the
+    /// lowest grid bit is actually used.
+    fn check_unit_f64<R: UniformRng>(name: &str) {
+        const SCALE: f64 = 9007199254740992.0; // 2^53
+        let mut r = R::from_seed(1);
+        let mut odd = false;
+        for _ in 0..100_000 {
+            let x = r.next_f64();
+            assert!((0.0..1.0).contains(&x), "{name}: {x} outside [0,1)");
+            let k = x * SCALE;
+            assert_eq!(k.fract(), 0.0, "{name}: {x} not a multiple of 2^-53");
+            odd |= k as u64 & 1 == 1;
+        }
+        assert!(odd, "{name}: lowest of the 53 bits never set");
+    }
+
+    #[test]
+    fn next_f64_is_in_unit_interval_with_53_bit_granularity() {
+        check_unit_f64::<XorShift128>("xorshift128");
+        check_unit_f64::<SplitMix32>("splitmix32");
+        check_unit_f64::<Pcg32>("pcg32");
+        check_unit_f64::<Xoshiro256StarStar>("xoshiro256ss");
+        check_unit_f64::<WyRand>("wyrand");
+    }
+
+    #[test]
+    fn next_f64_endpoints() {
+        assert_eq!(Constant(0).next_f64(), 0.0);
+        assert_eq!(
+            Constant(u32::MAX).next_f64(),
+            1.0 - 1.0 / 9007199254740992.0
+        );
+    }
+}
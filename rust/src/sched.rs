@@ -0,0 +1,146 @@
+//! CPU affinity, scheduling policy and which cores the runs landed on.
+//!
+//! Affinity and policy are set on the calling thread before any worker is
+//! spawned; Linux threads inherit both. Pinning therefore only makes sense
+//! for single-threaded runs, since every worker would share the one core.
+
+/// Scheduling policy selected with `--priority`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// `SCHED_OTHER`, the default time-sharing policy.
+    Normal,
+    /// `SCHED_FIFO` at the lowest real-time priority, which already
+    /// preempts every time-sharing task.
+    Fifo,
+}
+
+impl Priority {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::Normal => "normal",
+            Priority::Fifo => "fifo",
+        }
+    }
+}
+
+/// Restricts the calling thread to `cpu`.
+pub fn pin_to_cpu(cpu: usize) -> Result<(), String> {
+    sys::pin_to_cpu(cpu)
+}
+
+pub fn set_priority(priority: Priority) -> Result<(), String> {
+    sys::set_priority(priority)
+}
+
+/// Core the calling thread is running on, if the platform can tell.
+pub fn current_cpu() -> Option<usize> {
+    sys::current_cpu()
+}
+
+/// Cores seen by the timing thread, sampled once per timed run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuTrace {
+    /// Distinct cores, ascending.
+    pub cpus: Vec<usize>,
+    /// Runs that started on a different core than the previous one.
+    pub migrations: usize,
+    last: Option<usize>,
+}
+
+impl CpuTrace {
+    pub(crate) fn sample(&mut self) {
+        let Some(cpu) = current_cpu() else {
+            return;
+        };
+        if self.last.is_some_and(|last| last != cpu) {
+            self.migrations += 1;
+        }
+        self.last = Some(cpu);
+        if let Err(at) = self.cpus.binary_search(&cpu) {
+            self.cpus.insert(at, cpu);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::io;
+    use std::os::raw::c_int;
+
+    use super::Priority;
+
+    const SCHED_OTHER: c_int = 0;
+    const SCHED_FIFO: c_int = 1;
+    /// Bits in glibc's `cpu_set_t`.
+    const CPU_SETSIZE: usize = 1024;
+
+    #[repr(C)]
+    struct SchedParam {
+        sched_priority: c_int,
+    }
+
+    extern "C" {
+        fn sched_setaffinity(pid: c_int, cpusetsize: usize, mask: *const u64) -> c_int;
+        fn sched_setscheduler(pid: c_int, policy: c_int, param: *const SchedParam) -> c_int;
+        fn sched_get_priority_min(policy: c_int) -> c_int;
+        fn sched_getcpu() -> c_int;
+    }
+
+    pub(super) fn pin_to_cpu(cpu: usize) -> Result<(), String> {
+        if cpu >= CPU_SETSIZE {
+            return Err(format!("cpu {} is out of range (< {})", cpu, CPU_SETSIZE));
+        }
+        let mut mask = [0u64; CPU_SETSIZE / 64];
+        mask[cpu / 64] |= 1 << (cpu % 64);
+        if unsafe { sched_setaffinity(0, std::mem::size_of_val(&mask), mask.as_ptr()) } != 0 {
+            return Err(format!(
+                "sched_setaffinity(cpu {}) failed: {}",
+                cpu,
+                io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+
+    pub(super) fn set_priority(priority: Priority) -> Result<(), String> {
+        let (policy, sched_priority) = match priority {
+            Priority::Normal => (SCHED_OTHER, 0),
+            Priority::Fifo => (SCHED_FIFO, unsafe { sched_get_priority_min(SCHED_FIFO) }),
+        };
+        let param = SchedParam { sched_priority };
+        if unsafe { sched_setscheduler(0, policy, &param) } != 0 {
+            return Err(format!(
+                "sched_setscheduler({}) failed: {}",
+                priority.as_str(),
+                io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub(super) fn current_cpu() -> Option<usize> {
+        let cpu = unsafe { sched_getcpu() };
+        (cpu >= 0).then_some(cpu as usize)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use super::Priority;
+
+    pub(super) fn pin_to_cpu(_cpu: usize) -> Result<(), String> {
+        Err("CPU pinning needs Linux".to_string())
+    }
+
+    pub(super) fn set_priority(priority: Priority) -> Result<(), String> {
+        match priority {
+            Priority::Normal => Ok(()),
+            Priority::Fifo => Err("SCHED_FIFO needs Linux".to_string()),
+        }
+    }
+
+    pub(super) fn current_cpu() -> Option<usize> {
+        None
+    }
+}
@@ -0,0 +1,271 @@
+//! Descriptive statistics of per-run times.
+
+use crate::rng::XorShift128;
+
+/// Bootstrap resamples drawn for the median confidence interval.
+pub const BOOTSTRAP_RESAMPLES: usize = 1000;
+/// Fixed so the interval is reproducible for identical samples.
+const BOOTSTRAP_SEED: u32 = 0x5EED_B007;
+/// Largest sample the median interval is bootstrapped for; beyond it the
+/// O(resamples * n) bootstrap gives way to [`median_ci_order_stat`], whose
+/// normal approximation is accurate at that size anyway.
+pub const BOOTSTRAP_MAX_SAMPLES: usize = 2000;
+
+/// Summary of a sample, in the sample's own unit (except `cv`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    /// Sample standard deviation (n - 1 denominator); NaN for one sample.
+    pub stddev: f64,
+    /// Coefficient of variation, `stddev / mean`.
+    pub cv: f64,
+    pub min: f64,
+    pub p5: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+    /// Median absolute deviation from the median, unscaled.
+    pub mad: f64,
+    /// 95% confidence interval for the median: percentile bootstrap up to
+    /// [`BOOTSTRAP_MAX_SAMPLES`], order statistics above.
+    pub median_ci95: (f64, f64),
+}
+
+impl Summary {
+    /// Summarizes an ascending, non-empty slice.
+    pub fn from_sorted(xs: &[f64]) -> Self {
+        assert!(!xs.is_empty(), "cannot summarize an empty sample");
+        let count = xs.len();
+        let mean = xs.iter().sum::<f64>() / count as f64;
+        let stddev = if count > 1 {
+            let ss: f64 = xs.iter().map(|x| (x - mean) * (x - mean)).sum();
+            (ss / (count - 1) as f64).sqrt()
+        } else {
+            f64::NAN
+        };
+        let median = percentile_sorted(xs, 50.0);
+
+        let mut dev: Vec<f64> = xs.iter().map(|x| (x - median).abs()).collect();
+        dev.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let median_ci95 = if count > BOOTSTRAP_MAX_SAMPLES {
+            median_ci_order_stat(xs, 1.96)
+        } else {
+            bootstrap_median_ci(xs, 0.95, BOOTSTRAP_RESAMPLES)
+        };
+
+        Self {
+            count,
+            mean,
+            stddev,
+            cv: stddev / mean,
+            min: xs[0],
+            p5: percentile_sorted(xs, 5.0),
+            p25: percentile_sorted(xs, 25.0),
+            median,
+            p75: percentile_sorted(xs, 75.0),
+            p95: percentile_sorted(xs, 95.0),
+            p99: percentile_sorted(xs, 99.0),
+            max: xs[count - 1],
+            mad: percentile_sorted(&dev, 50.0),
+            median_ci95,
+        }
+    }
+}
+
+/// Linear-interpolation percentile (`p` in 0..=100) of an ascending slice.
+pub fn percentile_sorted(xs: &[f64], p: f64) -> f64 {
+    let last = xs.len() - 1;
+    let rank = p / 100.0 * last as f64;
+    let lo = rank.floor() as usize;
+    let hi = (lo + 1).min(last);
+    let frac = rank - lo as f64;
+    xs[lo] + (xs[hi] - xs[lo]) * frac
+}
+
+/// Percentile bootstrap confidence interval for the median.
+pub fn bootstrap_median_ci(xs: &[f64], level: f64, resamples: usize) -> (f64, f64) {
+    let len = xs.len();
+    if len < 2 {
+        return (xs[0], xs[0]);
+    }
+    let mut rng = XorShift128::new(BOOTSTRAP_SEED);
+    let mut buf = vec![0.0_f64; len];
+    let mut medians = Vec::with_capacity(resamples);
+    let mid = len / 2;
+    for _ in 0..resamples {
+        for v in buf.iter_mut() {
+            *v = xs[(rng.next_f64() * len as f64) as usize];
+        }
+        // Selection instead of a full sort keeps large run counts cheap.
+        let (lower, m, _) = buf.select_nth_unstable_by(mid, |a, b| a.partial_cmp(b).unwrap());
+        let median = if len % 2 == 1 {
+            *m
+        } else {
+            let below = lower.iter().fold(f64::NEG_INFINITY, |acc, &v| acc.max(v));
+            (below + *m) / 2.0
+        };
+        medians.push(median);
+    }
+    medians.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let tail = (1.0 - level) / 2.0 * 100.0;
+    (
+        percentile_sorted(&medians, tail),
+        percentile_sorted(&medians, 100.0 - tail),
+    )
+}
+
+/// Distribution-free confidence interval for the median from order
+/// statistics of an ascending slice: ranks `n/2 -+ z sqrt(n)/2`. O(1), so
+/// it is cheap enough to recheck while runs are still being collected.
+pub fn median_ci_order_stat(xs: &[f64], z: f64) -> (f64, f64) {
+    let len = xs.len();
+    let half = z * (len as f64).sqrt() / 2.0;
+    let mid = len as f64 / 2.0;
+    let lo = (mid - half).floor().max(0.0) as usize;
+    let hi = ((mid + half).ceil() as usize).min(len - 1);
+    (xs[lo], xs[hi])
+}
+
+/// Tukey-fence classification of a sample, as criterion reports it: mild
+/// outliers lie beyond 1.5 IQR of the quartiles, severe ones beyond 3 IQR.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Outliers {
+    pub low_severe: usize,
+    pub low_mild: usize,
+    pub high_mild: usize,
+    pub high_severe: usize,
+    /// Severe outliers dropped from the summary by `--reject-outliers`.
+    pub rejected: usize,
+    /// Fences `[low severe, low mild, high mild, high severe]`.
+    pub fences: [f64; 4],
+}
+
+impl Outliers {
+    /// Classifies an ascending, non-empty slice.
+    pub fn from_sorted(xs: &[f64]) -> Self {
+        let q1 = percentile_sorted(xs, 25.0);
+        let q3 = percentile_sorted(xs, 75.0);
+        let iqr = q3 - q1;
+        let fences = [
+            q1 - 3.0 * iqr,
+            q1 - 1.5 * iqr,
+            q3 + 1.5 * iqr,
+            q3 + 3.0 * iqr,
+        ];
+
+        let mut out = Self {
+            fences,
+            ..Self::default()
+        };
+        for &x in xs {
+            if x < fences[0] {
+                out.low_severe += 1;
+            } else if x < fences[1] {
+                out.low_mild += 1;
+            } else if x > fences[3] {
+                out.high_severe += 1;
+            } else if x > fences[2] {
+                out.high_mild += 1;
+            }
+        }
+        out
+    }
+
+    /// The part of the ascending slice `xs` between the severe fences.
+    pub fn without_severe<'a>(&self, xs: &'a [f64]) -> &'a [f64] {
+        &xs[self.low_severe..xs.len() - self.high_severe]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_interpolates_between_ranks() {
+        let xs = [1.0, 2.0, 3.0, 4.0];
+        assert_eq!(percentile_sorted(&xs, 0.0), 1.0);
+        assert_eq!(percentile_sorted(&xs, 25.0), 1.75);
+        assert_eq!(percentile_sorted(&xs, 50.0), 2.5);
+        assert_eq!(percentile_sorted(&xs, 100.0), 4.0);
+        assert_eq!(percentile_sorted(&[5.0], 99.0), 5.0);
+    }
+
+    #[test]
+    fn summary_of_a_small_sample() {
+        let s = Summary::from_sorted(&[1.0, 2.0, 3.0, 4.0, 10.0]);
+        assert_eq!(s.count, 5);
+        assert_eq!(s.mean, 4.0);
+        assert_eq!(s.stddev, 12.5_f64.sqrt());
+        assert_eq!(s.cv, s.stddev / 4.0);
+        assert_eq!((s.min, s.median, s.max), (1.0, 3.0, 10.0));
+        assert_eq!((s.p25, s.p75), (2.0, 4.0));
+        // Deviations from the median are 0, 1, 1, 2, 7.
+        assert_eq!(s.mad, 1.0);
+        let (lo, hi) = s.median_ci95;
+        assert!(1.0 <= lo && lo <= s.median && s.median <= hi && hi <= 10.0);
+    }
+
+    #[test]
+    fn single_sample_has_no_spread() {
+        let s = Summary::from_sorted(&[0.25]);
+        assert!(s.stddev.is_nan());
+        assert!(s.cv.is_nan());
+        assert_eq!(s.mad, 0.0);
+        assert_eq!(s.median_ci95, (0.25, 0.25));
+    }
+
+    /// Nine points with `q1 = 0` and `q3 = 10`, so the fences are -30, -15,
+    /// 25 and 40.
+    fn nine(low: [f64; 2], high: [f64; 2]) -> [f64; 9] {
+        [low[0], low[1], 0.0, 5.0, 5.0, 5.0, 10.0, high[0], high[1]]
+    }
+
+    #[test]
+    fn outlier_fences_are_strict() {
+        // On a mild fence a point is not an outlier at all.
+        let xs = nine([-15.0, -15.0], [25.0, 25.0]);
+        let o = Outliers::from_sorted(&xs);
+        assert_eq!(o.fences, [-30.0, -15.0, 25.0, 40.0]);
+        assert_eq!(
+            (o.low_severe, o.low_mild, o.high_mild, o.high_severe),
+            (0, 0, 0, 0)
+        );
+        assert_eq!(o.without_severe(&xs), &xs[..]);
+
+        // On a severe fence a point is only mild.
+        let xs = nine([-30.0, -16.0], [26.0, 40.0]);
+        let o = Outliers::from_sorted(&xs);
+        assert_eq!(
+            (o.low_severe, o.low_mild, o.high_mild, o.high_severe),
+            (0, 2, 2, 0)
+        );
+    }
+
+    #[test]
+    fn without_severe_drops_only_the_severe_tails() {
+        let xs = nine([-31.0, -16.0], [26.0, 41.0]);
+        let o = Outliers::from_sorted(&xs);
+        assert_eq!(
+            (o.low_severe, o.low_mild, o.high_mild, o.high_severe),
+            (1, 1, 1, 1)
+        );
+        assert_eq!(o.without_severe(&xs), &xs[1..8]);
+    }
+
+    #[test]
+    fn large_samples_use_the_order_statistic_interval() {
+        let xs: Vec<f64> = (0..=BOOTSTRAP_MAX_SAMPLES).map(|i| i as f64).collect();
+        let s = Summary::from_sorted(&xs);
+        assert_eq!(s.median_ci95, median_ci_order_stat(&xs, 1.96));
+        let small = Summary::from_sorted(&xs[..BOOTSTRAP_MAX_SAMPLES]);
+        assert_eq!(
+            small.median_ci95,
+            bootstrap_median_ci(&xs[..BOOTSTRAP_MAX_SAMPLES], 0.95, BOOTSTRAP_RESAMPLES)
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! When to stop warming up and when to stop collecting timed runs.
+
+use std::time::{Duration, Instant};
+
+use crate::stats;
+
+/// Runs collected before the precision target is first checked.
+pub const MIN_ADAPTIVE_RUNS: usize = 10;
+
+/// Adaptive run count: stop once the median is precise enough, the time
+/// budget is spent, or `max_runs` is reached, whichever comes first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adaptive {
+    /// Target relative half-width of the median's 95% confidence interval.
+    pub target_rel_ci: Option<f64>,
+    /// Wall-clock budget for the timed runs; warmup is not counted.
+    pub time_budget: Option<Duration>,
+    pub max_runs: usize,
+}
+
+impl Default for Adaptive {
+    fn default() -> Self {
+        Self {
+            target_rel_ci: None,
+            time_budget: None,
+            max_runs: 100_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The fixed `--runs` count was reached.
+    Runs,
+    TargetReached,
+    TimeBudget,
+    MaxRuns,
+}
+
+impl StopReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StopReason::Runs => "runs",
+            StopReason::TargetReached => "target_rel_ci",
+            StopReason::TimeBudget => "time_budget",
+            StopReason::MaxRuns => "max_runs",
+        }
+    }
+}
+
+/// Decides after each run whether to run again.
+pub(crate) struct StopRule {
+    runs: usize,
+    adaptive: Option<Adaptive>,
+    started: Instant,
+    /// Run count at which the precision target is next checked; checks are
+    /// spaced geometrically so their sorting cost stays O(n log n) overall.
+    next_check: usize,
+    scratch: Vec<f64>,
+    pub(crate) reason: StopReason,
+}
+
+impl StopRule {
+    pub(crate) fn new(runs: usize, adaptive: Option<Adaptive>) -> Self {
+        Self {
+            runs,
+            adaptive,
+            started: Instant::now(),
+            next_check: MIN_ADAPTIVE_RUNS,
+            scratch: Vec::new(),
+            reason: StopReason::Runs,
+        }
+    }
+
+    /// `run_times` holds the runs so far, in run order.
+    pub(crate) fn done(&mut self, run_times: &[f64]) -> bool {
+        let count = run_times.len();
+        let Some(ad) = self.adaptive else {
+            return count >= self.runs;
+        };
+        // Always collect at least one run.
+        if count == 0 {
+            return false;
+        }
+
+        if count >= ad.max_runs {
+            self.reason = StopReason::MaxRuns;
+            return true;
+        }
+        if let Some(budget) = ad.time_budget {
+            if self.started.elapsed() >= budget {
+                self.reason = StopReason::TimeBudget;
+                return true;
+            }
+        }
+        if let Some(target) = ad.target_rel_ci {
+            if count >= self.next_check {
+                self.next_check = count + (count / 10).max(1);
+                self.scratch.clear();
+                self.scratch.extend_from_slice(run_times);
+                self.scratch.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let (lo, hi) = stats::median_ci_order_stat(&self.scratch, 1.96);
+                let median = stats::percentile_sorted(&self.scratch, 50.0);
+                if (hi - lo) / (2.0 * median) <= target {
+                    self.reason = StopReason::TargetReached;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Warmup iteration count, fixed or detected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Warmup {
+    Fixed(usize),
+    Auto(AutoWarmup),
+}
+
+/// Warm up until the last `window` iteration times stop drifting: the
+/// least-squares trend across the window, relative to its mean, must fall
+/// below `max_rel_drift`. Gives up after `max_iters`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoWarmup {
+    pub window: usize,
+    pub max_rel_drift: f64,
+    pub max_iters: usize,
+}
+
+impl Default for AutoWarmup {
+    fn default() -> Self {
+        Self {
+            window: 10,
+            max_rel_drift: 0.05,
+            max_iters: 200,
+        }
+    }
+}
+
+/// Decides after each warmup iteration whether to run another.
+pub(crate) struct WarmupRule {
+    warmup: Warmup,
+    /// Whether auto warmup saw a stable window before `max_iters`.
+    pub(crate) converged: Option<bool>,
+}
+
+impl WarmupRule {
+    pub(crate) fn new(warmup: Warmup) -> Self {
+        Self {
+            warmup,
+            converged: None,
+        }
+    }
+
+    /// `times` holds the warmup iteration times so far.
+    pub(crate) fn done(&mut self, times: &[f64]) -> bool {
+        let auto = match self.warmup {
+            Warmup::Fixed(n) => return times.len() >= n,
+            Warmup::Auto(auto) => auto,
+        };
+        let count = times.len();
+        let window = auto.window.max(2);
+        if count >= window && relative_drift(&times[count - window..]) < auto.max_rel_drift {
+            self.converged = Some(true);
+            return true;
+        }
+        if count >= auto.max_iters {
+            self.converged = Some(false);
+            return true;
+        }
+        false
+    }
+}
+
+/// Absolute least-squares change across `ys`, relative to their mean.
+fn relative_drift(ys: &[f64]) -> f64 {
+    let len = ys.len() as f64;
+    let x_mean = (len - 1.0) / 2.0;
+    let y_mean = ys.iter().sum::<f64>() / len;
+    let (mut sxy, mut sxx) = (0.0_f64, 0.0_f64);
+    for (i, y) in ys.iter().enumerate() {
+        let dx = i as f64 - x_mean;
+        sxy += dx * (y - y_mean);
+        sxx += dx * dx;
+    }
+    (sxy / sxx * (len - 1.0)).abs() / y_mean
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adaptive(target_rel_ci: Option<f64>, max_runs: usize) -> Option<Adaptive> {
+        Some(Adaptive {
+            target_rel_ci,
+            time_budget: None,
+            max_runs,
+        })
+    }
+
+    #[test]
+    fn fixed_runs_stop_at_the_count() {
+        let mut rule = StopRule::new(3, None);
+        assert!(!rule.done(&[1.0, 1.0]));
+        assert!(rule.done(&[1.0, 1.0, 1.0]));
+        assert_eq!(rule.reason, StopReason::Runs);
+    }
+
+    #[test]
+    fn adaptive_limits_set_the_reason() {
+        let mut rule = StopRule::new(0, adaptive(None, 4));
+        assert!(!rule.done(&[1.0; 3]));
+        assert!(rule.done(&[1.0; 4]));
+        assert_eq!(rule.reason, StopReason::MaxRuns);
+
+        let mut rule = StopRule::new(
+            0,
+            Some(Adaptive {
+                time_budget: Some(Duration::ZERO),
+                ..Adaptive::default()
+            }),
+        );
+        assert!(!rule.done(&[]), "at least one run is always collected");
+        assert!(rule.done(&[1.0]));
+        assert_eq!(rule.reason, StopReason::TimeBudget);
+    }
+
+    #[test]
+    fn target_is_first_checked_after_the_minimum_runs() {
+        let times = [1.0; MIN_ADAPTIVE_RUNS];
+        let mut rule = StopRule::new(0, adaptive(Some(0.01), 1000));
+        for count in 1..MIN_ADAPTIVE_RUNS {
+            assert!(!rule.done(&times[..count]));
+        }
+        assert!(rule.done(&times));
+        assert_eq!(rule.reason, StopReason::TargetReached);
+    }
+
+    #[test]
+    fn target_checks_are_spaced_geometrically() {
+        // Distinct times keep the interval open, so the target is never met.
+        let times: Vec<f64> = (0..10_000).map(|i| 1.0 + i as f64).collect();
+        let mut rule = StopRule::new(0, adaptive(Some(1e-12), usize::MAX));
+        let mut checks = Vec::new();
+        for count in 1..=times.len() {
+            let due = rule.next_check;
+            assert!(!rule.done(&times[..count]));
+            if count == due {
+                checks.push(count);
+            }
+        }
+        assert_eq!(
+            checks[..17],
+            [10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 22, 24, 26, 28, 30, 33]
+        );
+        for w in checks.windows(2) {
+            assert_eq!(w[1] - w[0], (w[0] / 10).max(1));
+        }
+        assert!(checks.len() < 100, "{} checks", checks.len());
+    }
+
+    #[test]
+    fn drift_is_the_fitted_change_over_the_mean() {
+        assert_eq!(relative_drift(&[2.0; 5]), 0.0);
+        // Slope 1 over 4 steps, mean 3.
+        assert_eq!(relative_drift(&[1.0, 2.0, 3.0, 4.0, 5.0]), 4.0 / 3.0);
+        assert_eq!(relative_drift(&[5.0, 4.0, 3.0, 2.0, 1.0]), 4.0 / 3.0);
+        // Noise without a trend does not count as drift.
+        assert_eq!(relative_drift(&[1.0, 3.0, 3.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn auto_warmup_converges_or_gives_up() {
+        let auto = AutoWarmup {
+            window: 4,
+            max_rel_drift: 0.05,
+            max_iters: 8,
+        };
+        let mut rule = WarmupRule::new(Warmup::Auto(auto));
+        let settling = [3.0, 2.0, 1.0, 1.0, 1.0, 1.0];
+        let done: Vec<bool> = (1..=settling.len())
+            .map(|n| rule.done(&settling[..n]))
+            .collect();
+        assert_eq!(done, [false, false, false, false, false, true]);
+        assert_eq!(rule.converged, Some(true));
+
+        let mut rule = WarmupRule::new(Warmup::Auto(auto));
+        let falling: Vec<f64> = (0..8).map(|i| 10.0 - i as f64).collect();
+        assert!(!rule.done(&falling[..7]));
+        assert!(rule.done(&falling));
+        assert_eq!(rule.converged, Some(false));
+
+        let mut rule = WarmupRule::new(Warmup::Fixed(2));
+        assert!(!rule.done(&[5.0]));
+        assert!(rule.done(&[5.0, 4.0]));
+        assert_eq!(rule.converged, None);
+    }
+}
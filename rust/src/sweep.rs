@@ -0,0 +1,164 @@
+//! Sweeps over `n` and the linear scaling fit across them.
+
+use crate::bench::{self, Config, Report, Throughput};
+
+/// Per-step phase costs of one sweep point, on the same basis as
+/// [`Throughput`]: `(n - 1) * paths` normals and OU steps per run, and per
+/// value summed for the checksum. Phases the mode skips are NaN.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SweepPoint {
+    pub n: usize,
+    pub median_s: f64,
+    pub gen_ns_per_step: f64,
+    pub sim_ns_per_step: f64,
+    pub chk_ns_per_step: f64,
+}
+
+impl SweepPoint {
+    pub fn new(cfg: &Config, report: &Report) -> Self {
+        let tp = Throughput::new(cfg, report);
+        let f64_bytes = std::mem::size_of::<f64>() as f64;
+        Self {
+            n: cfg.n,
+            median_s: report.median_s,
+            gen_ns_per_step: tp.ns_per_normal,
+            sim_ns_per_step: tp.ns_per_ou_step,
+            chk_ns_per_step: f64_bytes / tp.chk_bytes_per_s * 1e9,
+        }
+    }
+}
+
+/// Ordinary least-squares fit `y = slope * x + intercept`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    /// Coefficient of determination.
+    pub r2: f64,
+}
+
+impl LinearFit {
+    /// Needs at least two distinct `xs`; otherwise every field is NaN.
+    pub fn fit(xs: &[f64], ys: &[f64]) -> Self {
+        let len = xs.len() as f64;
+        let x_mean = xs.iter().sum::<f64>() / len;
+        let y_mean = ys.iter().sum::<f64>() / len;
+        let (mut sxy, mut sxx, mut syy) = (0.0_f64, 0.0_f64, 0.0_f64);
+        for (x, y) in xs.iter().zip(ys) {
+            sxy += (x - x_mean) * (y - y_mean);
+            sxx += (x - x_mean) * (x - x_mean);
+            syy += (y - y_mean) * (y - y_mean);
+        }
+        if sxx == 0.0 {
+            return Self {
+                slope: f64::NAN,
+                intercept: f64::NAN,
+                r2: f64::NAN,
+            };
+        }
+        let slope = sxy / sxx;
+        Self {
+            slope,
+            intercept: y_mean - slope * x_mean,
+            r2: if syy == 0.0 {
+                1.0
+            } else {
+                sxy * sxy / (sxx * syy)
+            },
+        }
+    }
+}
+
+/// Runs the benchmark once per `n`, keeping every other setting of `cfg`.
+pub fn run_sweep(cfg: &Config, ns: &[usize]) -> Vec<(Config, Report)> {
+    ns.iter()
+        .map(|&n| {
+            let point_cfg = Config { n, ..*cfg };
+            let report = bench::run(&point_cfg);
+            (point_cfg, report)
+        })
+        .collect()
+}
+
+/// Fits median run time (seconds) against `n`.
+pub fn fit_median(points: &[SweepPoint]) -> LinearFit {
+    let xs: Vec<f64> = points.iter().map(|p| p.n as f64).collect();
+    let ys: Vec<f64> = points.iter().map(|p| p.median_s).collect();
+    LinearFit::fit(&xs, &ys)
+}
+
+/// Parses `1000,10000,100000` or a geometric range `start:end:ratio`
+/// such as `1000:1000000:10` (end included when hit exactly).
+pub fn parse_sweep_spec(spec: &str) -> Option<Vec<usize>> {
+    let ns: Vec<usize> = if let Some((start, rest)) = spec.split_once(':') {
+        let (end, ratio) = rest.split_once(':')?;
+        let start: usize = start.parse().ok()?;
+        let end: usize = end.parse().ok()?;
+        let ratio: f64 = ratio.parse().ok()?;
+        if start == 0 || ratio <= 1.0 {
+            return None;
+        }
+        let mut ns = Vec::new();
+        let mut x = start as f64;
+        // Small tolerance so 10^k steps are not lost to rounding.
+        while x <= end as f64 * (1.0 + 1e-9) {
+            ns.push(x.round() as usize);
+            x *= ratio;
+        }
+        ns.dedup();
+        ns
+    } else {
+        spec.split(',')
+            .map(|s| s.trim().parse().ok())
+            .collect::<Option<_>>()?
+    };
+    (!ns.is_empty()).then_some(ns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geometric_spec_rounds_and_dedups() {
+        assert_eq!(
+            parse_sweep_spec("1000:100000:10"),
+            Some(vec![1000, 10000, 100000])
+        );
+        // 10, 11, 12.1, 13.31, 14.64, 16.1, 17.72, 19.49.
+        assert_eq!(
+            parse_sweep_spec("10:20:1.1"),
+            Some(vec![10, 11, 12, 13, 15, 16, 18, 19])
+        );
+        // 2, 2.4, 2.88, 3.46 round to 2, 2, 3, 3.
+        assert_eq!(parse_sweep_spec("2:4:1.2"), Some(vec![2, 3]));
+    }
+
+    #[test]
+    fn list_spec_and_invalid_specs() {
+        assert_eq!(parse_sweep_spec("100, 200,300"), Some(vec![100, 200, 300]));
+        for bad in [
+            "", "0:10:2", "10:100:1", "10:100", "10:x:2", "100,,200", "1e3",
+        ] {
+            assert_eq!(parse_sweep_spec(bad), None, "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn fit_recovers_a_line() {
+        let f = LinearFit::fit(&[1.0, 2.0, 4.0], &[5.0, 7.0, 11.0]);
+        assert_eq!((f.slope, f.intercept, f.r2), (2.0, 3.0, 1.0));
+        let flat = LinearFit::fit(&[1.0, 2.0], &[4.0, 4.0]);
+        assert_eq!((flat.slope, flat.intercept, flat.r2), (0.0, 4.0, 1.0));
+        let noisy = LinearFit::fit(&[0.0, 1.0, 2.0, 3.0], &[0.0, 2.0, 1.0, 3.0]);
+        assert!(noisy.r2 > 0.0 && noisy.r2 < 1.0);
+    }
+
+    #[test]
+    fn fit_without_distinct_xs_is_nan() {
+        for xs in [&[3.0][..], &[3.0, 3.0]] {
+            let f = LinearFit::fit(xs, &vec![1.0; xs.len()]);
+            assert!(f.slope.is_nan() && f.intercept.is_nan() && f.r2.is_nan());
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Known-good results for `--verify`.
+//!
+//! Checksums depend on the platform's `ln` (the polar sampler) and, for
+//! the exact scheme, `exp`, so references are kept per target triple. Each
+//! checksum covers the timed runs only: those always start from fresh
+//! generator streams, so warmup, clock and outlier settings do not enter.
+//! Values are stored as f64 bit patterns and compared bit for bit.
+
+use crate::bench::{Config, Mode};
+use crate::environment::BUILD;
+use crate::normal::{NormalKind, NormalPolar};
+use crate::ou::{self, OuParams, Scheme};
+use crate::rng::{RngKind, UniformRng, XorShift128};
+
+/// Leading values compared by [`check_prefix`].
+pub const PREFIX_LEN: usize = 8;
+
+/// Seed and `n` of the prefix values.
+pub const PREFIX_SEED: u32 = 1;
+pub const PREFIX_N: usize = 500_000;
+
+/// Checksum of one standard configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Golden {
+    pub n: usize,
+    pub runs: usize,
+    pub seed: u32,
+    pub mode: Mode,
+    pub scheme: Scheme,
+    pub checksum: u64,
+}
+
+/// First [`PREFIX_LEN`] values of the reference generator and sampler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Prefix {
+    /// Standard normals from `XorShift128` seeded with [`PREFIX_SEED`].
+    pub normals: [u64; PREFIX_LEN],
+    /// OU values after `x0` with default parameters at [`PREFIX_N`], driven
+    /// by the same normals.
+    pub ou_euler: [u64; PREFIX_LEN],
+    pub ou_exact: [u64; PREFIX_LEN],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reference {
+    pub target: &'static str,
+    pub checksums: &'static [Golden],
+    pub prefix: Option<Prefix>,
+}
+
+const fn golden(
+    n: usize,
+    runs: usize,
+    seed: u32,
+    mode: Mode,
+    scheme: Scheme,
+    checksum: u64,
+) -> Golden {
+    Golden {
+        n,
+        runs,
+        seed,
+        mode,
+        scheme,
+        checksum,
+    }
+}
+
+#[rustfmt::skip]
+pub const REFERENCES: &[Reference] = &[
+    Reference {
+        target: "x86_64-unknown-linux-gnu",
+        checksums: &[
+            // run_all.sh defaults
+            golden(500_000, 1000, 1, Mode::Full, Scheme::Euler, 0x41229CBC34344A4D),
+            golden(500_000, 1000, 1, Mode::Gn, Scheme::Euler, 0x40117EAC65ADF0C1),
+            golden(500_000, 1000, 1, Mode::Ou, Scheme::Euler, 0x4165919276E1BD12),
+            golden(500_000, 1000, 1, Mode::Full, Scheme::Exact, 0x41229CBB47A080CE),
+            golden(500_000, 1000, 1, Mode::Gn, Scheme::Exact, 0x40117EAB402A558B),
+            golden(500_000, 1000, 1, Mode::Ou, Scheme::Exact, 0x41659191700498F0),
+            // Quick check
+            golden(10_000, 20, 7, Mode::Full, Scheme::Euler, 0xC0A90358CC23F2FB),
+            golden(10_000, 20, 7, Mode::Gn, Scheme::Euler, 0xBFEC0CEFA668BCEC),
+            golden(10_000, 20, 7, Mode::Ou, Scheme::Euler, 0xC097FA96FE40C366),
+            golden(10_000, 20, 7, Mode::Full, Scheme::Exact, 0xC0A9031E521E1A9F),
+            golden(10_000, 20, 7, Mode::Gn, Scheme::Exact, 0xBFEC0C93BCE5E48C),
+            golden(10_000, 20, 7, Mode::Ou, Scheme::Exact, 0xC097FA5A18CC5CA6),
+        ],
+        prefix: Some(Prefix {
+            normals: [
+                0x3FECE8C80ACACC26,
+                0xBF943B65B53D294F,
+                0xBFB713D32073F093,
+                0x3FC18C32AEF02F86,
+                0xBFDCFEFB8DBC7FBC,
+                0x3F93274C006831D5,
+                0xBFEB882D4C2B9303,
+                0x3FF055C613FE79F7,
+            ],
+            ou_euler: [
+                0x3F20BEFD92B510E8,
+                0x3F2061398F1B8ED1,
+                0x3F1D6AE374D48CF4,
+                0x3F213FF9FA5C6AC9,
+                0x3F11B4159E8FBE24,
+                0x3F126597FC702D3A,
+                0xBF0AFFCACEC8BBCC,
+                0x3F18597AACEE15D2,
+            ],
+            ou_exact: [
+                0x3F20BEFC79C15C2B,
+                0x3F2061387C4D1F19,
+                0x3F1D6AE18749E1C4,
+                0x3F213FF8D8F5193B,
+                0x3F11B414758D2A19,
+                0x3F126596C7CBA459,
+                0xBF0AFFC909CD57C4,
+                0x3F185979146B0EC1,
+            ],
+        }),
+    },
+    Reference {
+        target: "aarch64-apple-darwin",
+        // From DOCS/Run-Record-2025-12-19.md.
+        checksums: &[
+            golden(500_000, 1000, 1, Mode::Full, Scheme::Euler, 0x41229CBC34344A4F),
+        ],
+        prefix: None,
+    },
+];
+
+/// References for the target this binary was built for.
+pub fn reference() -> Option<&'static Reference> {
+    REFERENCES.iter().find(|r| r.target == BUILD.target)
+}
+
+/// One bit-for-bit comparison.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+impl Check {
+    pub fn ok(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+/// Golden checksum for `cfg` after `runs` timed runs, if it is a standard
+/// configuration: reference generator, sampler and OU parameters on one
+/// path and one thread.
+pub fn golden_checksum(reference: &Reference, cfg: &Config, runs: usize) -> Option<u64> {
+    let standard = cfg.rng == RngKind::XorShift128
+        && cfg.normal == NormalKind::Polar
+        && cfg.paths == 1
+        && cfg.threads == 1
+        && cfg.params == OuParams::default();
+    if !standard {
+        return None;
+    }
+    reference
+        .checksums
+        .iter()
+        .find(|g| {
+            g.n == cfg.n
+                && g.runs == runs
+                && g.seed == cfg.seed
+                && g.mode == cfg.mode
+                && g.scheme == cfg.scheme
+        })
+        .map(|g| g.checksum)
+}
+
+/// Recomputes the prefix values and compares them with `prefix`.
+pub fn check_prefix(prefix: &Prefix) -> Vec<Check> {
+    let mut rng = XorShift128::from_seed(PREFIX_SEED);
+    let mut norm = NormalPolar::default();
+    let normals: Vec<f64> = (0..PREFIX_LEN)
+        .map(|_| norm.next_standard(&mut rng))
+        .collect();
+
+    let mut checks = Vec::new();
+    let mut push = |what: &str, expected: &[u64; PREFIX_LEN], actual: &[f64]| {
+        for (i, (e, a)) in expected.iter().zip(actual).enumerate() {
+            checks.push(Check {
+                name: format!("{}[{}]", what, i),
+                expected: *e,
+                actual: a.to_bits(),
+            });
+        }
+    };
+    push("normals", &prefix.normals, &normals);
+
+    let params = OuParams::default();
+    for (scheme, expected) in [
+        (Scheme::Euler, &prefix.ou_euler),
+        (Scheme::Exact, &prefix.ou_exact),
+    ] {
+        let c = params.coefficients(scheme, PREFIX_N);
+        let gn: Vec<f64> = normals.iter().map(|z| c.diff * z).collect();
+        let mut xs = vec![0.0_f64; PREFIX_LEN + 1];
+        ou::simulate(&mut xs, &gn, c.a, c.b, params.x0);
+        push(&format!("ou_{}", scheme.as_str()), expected, &xs[1..]);
+    }
+    checks
+}